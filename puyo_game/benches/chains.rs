@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::prelude::*;
+
+use puyo_game::game::{Board, BitBoard, BOARD_WIDTH, BOARD_HEIGHT};
+use puyo_game::game::chain::ChainTracker;
+use puyo_game::game::color::Color;
+use puyo_game::game::color::Color::*;
+use puyo_game::game::util::{Coord, Direction};
+
+// Settled boards with a mix of colors and garbage, so every chain check has
+// groups to pop, groups too small to pop and garbage next to both.
+fn sample_boards() -> Vec<Board> {
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let colors = [Red, Green, Blue, Yellow, Garbage];
+    (0..64).map(|_| {
+        let mut bits = BitBoard::new();
+        for x in 0..BOARD_WIDTH {
+            let height = rng.gen_range(0, BOARD_HEIGHT - 1);
+            for y in 0..height {
+                let coord = Coord { x: x as i32, y: y as i32 };
+                bits.set(&coord, *colors.choose(&mut rng).unwrap());
+            }
+        }
+        Board::from(&bits)
+    }).collect()
+}
+
+fn to_grid(board: &Board) -> Vec<Vec<Color>> {
+    (0..BOARD_HEIGHT).map(|y| {
+        (0..BOARD_WIDTH).map(|x| {
+            board.get(&Coord { x: x as i32, y: y as i32 }).unwrap()
+        }).collect()
+    }).collect()
+}
+
+// The flood fill `check_chains` used before it moved to a visited grid,
+// kept here as the baseline to measure against.
+fn hashset_pop_groups(cells: &mut [Vec<Color>], chain: &mut ChainTracker)
+        -> bool {
+    let mut not_part_of_chain: HashSet<Coord> = HashSet::new();
+    let mut pending_coords = HashSet::new();
+    let mut any_cleared = false;
+
+    for y in 0i32..((BOARD_HEIGHT - 1) as i32) {
+        for x in 0i32..(BOARD_WIDTH as i32) {
+            let mut found_coords = HashSet::new();
+            let mut garbage_coords = HashSet::new();
+            let coord = Coord { x, y };
+            if not_part_of_chain.contains(&coord) {
+                continue;
+            }
+            let blob_color = match cells[y as usize][x as usize] {
+                color if color.is_normal() => color,
+                _ => continue,
+            };
+            pending_coords.insert(coord);
+            while let Some(item) = pending_coords.iter().next() {
+                let item = *item;
+                pending_coords.remove(&item);
+                let color = cells[item.y as usize][item.x as usize];
+                if color == Garbage {
+                    garbage_coords.insert(item);
+                    continue;
+                } else if color != blob_color {
+                    continue;
+                }
+                for dir in Direction::each_real() {
+                    let possible = item.apply_motion(*dir);
+                    if not_part_of_chain.contains(&possible)
+                        || found_coords.contains(&possible)
+                        || possible.x < 0
+                        || possible.y < 0
+                        || possible.x >= BOARD_WIDTH as i32
+                        || possible.y >= (BOARD_HEIGHT - 1) as i32 {
+                        continue;
+                    }
+                    pending_coords.insert(possible);
+                }
+                found_coords.insert(item);
+            }
+            if found_coords.len() < 4 {
+                not_part_of_chain.extend(found_coords);
+            } else {
+                any_cleared = true;
+                for puyo in found_coords.iter().chain(garbage_coords.iter()) {
+                    cells[puyo.y as usize][puyo.x as usize] = Empty;
+                }
+                chain.record_group(blob_color, found_coords.len() as u32,
+                                   garbage_coords.len() as u32);
+            }
+        }
+    }
+    if any_cleared {
+        chain.end_cycle();
+    }
+    any_cleared
+}
+
+fn pop_groups(c: &mut Criterion) {
+    let boards = sample_boards();
+    let grids: Vec<_> = boards.iter().map(to_grid).collect();
+    let bitboards: Vec<_> = boards.iter().map(BitBoard::from).collect();
+
+    let mut group = c.benchmark_group("pop_groups");
+    group.bench_function("hashset", |b| b.iter(|| {
+        for grid in &grids {
+            let mut grid = grid.clone();
+            hashset_pop_groups(&mut grid, &mut ChainTracker::new());
+        }
+    }));
+    group.bench_function("board", |b| b.iter(|| {
+        for board in &boards {
+            let mut board = *board;
+            board.pop_groups(&mut ChainTracker::new());
+        }
+    }));
+    group.bench_function("bitboard", |b| b.iter(|| {
+        for bits in &bitboards {
+            let mut bits = *bits;
+            bits.pop_groups(&mut ChainTracker::new());
+        }
+    }));
+    group.finish();
+}
+
+fn resolve_chains(c: &mut Criterion) {
+    let boards = sample_boards();
+    let bitboards: Vec<_> = boards.iter().map(BitBoard::from).collect();
+
+    let mut group = c.benchmark_group("resolve_chains");
+    group.bench_function("board", |b| b.iter(|| {
+        for board in &boards {
+            let mut board = *board;
+            board.resolve_chains();
+        }
+    }));
+    group.bench_function("bitboard", |b| b.iter(|| {
+        for bits in &bitboards {
+            let mut bits = *bits;
+            bits.resolve_chains();
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, pop_groups, resolve_chains);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+//! A bot for `puyo_host` that answers each turn with the library's own
+//! search, looking at the current pair and the first pair in the queue.
+//!
+//! cargo build --example greedy_bot
+//! cargo run --bin puyo_host -- target/debug/examples/greedy_bot
+
+use std::io::{self, BufRead, Write};
+
+use puyo_game::ai::search;
+use puyo_game::bot::{Message, Move, Orientation};
+use puyo_game::game::color::pair_from_str;
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let Message::Turn(turn) = match serde_json::from_str(&line?) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        let board = match turn.board() {
+            Some(board) => board,
+            None => continue,
+        };
+        let pairs: Vec<_> = Some(&turn.current).into_iter()
+            .chain(turn.queue.iter().take(1))
+            .filter_map(|pair| pair_from_str(pair))
+            .collect();
+        if pairs.is_empty() {
+            continue;
+        }
+        let best = board.legal_placements().into_iter()
+            .max_by_key(|placement| search::value(&board, &pairs, placement));
+        let chosen = best.and_then(|best| {
+            Orientation::from_direction(best.orientation)
+                .map(|orientation| Move {
+                    turn: turn.turn,
+                    column: best.column,
+                    orientation,
+                })
+        });
+        if let Some(reply) = chosen {
+            writeln!(out, "{}", serde_json::to_string(&reply)?)?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
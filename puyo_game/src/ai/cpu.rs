@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+
+use crate::game::{Game, Input, Controller};
+use super::search;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+struct Settings {
+    // how many pairs to look ahead through, counting the current one
+    lookahead: usize,
+    // random amount added to each placement's value
+    noise: i32,
+    // ticks to wait before the first input for a new pair
+    think_ticks: u32,
+    // ticks to wait between inputs
+    input_ticks: u32,
+    // whether to hold down once the pair is in place
+    soft_drop: bool,
+}
+
+impl Difficulty {
+    fn settings(self) -> Settings {
+        match self {
+            Difficulty::Easy => Settings {
+                lookahead: 1,
+                noise: 400,
+                think_ticks: 30,
+                input_ticks: 10,
+                soft_drop: false,
+            },
+            Difficulty::Normal => Settings {
+                lookahead: 1,
+                noise: 40,
+                think_ticks: 15,
+                input_ticks: 5,
+                soft_drop: true,
+            },
+            Difficulty::Hard => Settings {
+                lookahead: 2,
+                noise: 0,
+                think_ticks: 4,
+                input_ticks: 2,
+                soft_drop: true,
+            },
+        }
+    }
+}
+
+/// A computer player that steers a `Game` with the same inputs a person
+/// would use.
+pub struct Cpu {
+    difficulty: Difficulty,
+    rng: StdRng,
+    planned_for: Option<u32>,
+    plan: VecDeque<Input>,
+    wait: u32,
+}
+
+impl Cpu {
+    pub fn new(difficulty: Difficulty) -> Cpu {
+        Cpu::with_rng(difficulty, StdRng::from_entropy())
+    }
+
+    pub fn with_seed(difficulty: Difficulty, seed: u64) -> Cpu {
+        Cpu::with_rng(difficulty, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(difficulty: Difficulty, rng: StdRng) -> Cpu {
+        Cpu {
+            difficulty,
+            rng,
+            planned_for: None,
+            plan: VecDeque::new(),
+            wait: 0,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    fn next_input(&mut self, game: &Game) -> Option<Input> {
+        if game.is_over() || game.current_pair().is_none() {
+            return None;
+        }
+        let settings = self.difficulty.settings();
+        if self.planned_for != Some(game.pair_number()) {
+            self.planned_for = Some(game.pair_number());
+            self.plan = self.choose(game, &settings).into();
+            self.wait = settings.think_ticks;
+        }
+        if self.wait > 0 {
+            self.wait -= 1;
+            return None;
+        }
+        match self.plan.pop_front() {
+            Some(input) => {
+                self.wait = settings.input_ticks;
+                Some(input)
+            }
+            None if settings.soft_drop => Some(Input::Down),
+            None => None,
+        }
+    }
+
+    fn choose(&mut self, game: &Game, settings: &Settings) -> Vec<Input> {
+        let current = match game.current_pair() {
+            Some(pair) => pair,
+            None => return Vec::new(),
+        };
+        let pairs = [current, game.next_puyo()];
+        let pairs = &pairs[..settings.lookahead];
+        let stack = game.stack();
+        let placements = game.legal_placements();
+        let rng = &mut self.rng;
+        placements.into_iter()
+            .map(|placement| {
+                let noise = rng.gen_range(0, settings.noise + 1);
+                let value = search::value(&stack, pairs, &placement);
+                (value.saturating_add(noise), placement)
+            })
+            .max_by_key(|(value, _)| *value)
+            .map(|(_, placement)| placement.inputs)
+            .unwrap_or_default()
+    }
+}
+
+impl Controller for Cpu {
+    fn poll(&mut self, game: &Game) -> Vec<Input> {
+        self.next_input(game).into_iter().collect()
+    }
+
+    fn reset(&mut self) {
+        self.planned_for = None;
+        self.plan.clear();
+        self.wait = 0;
+    }
+}
@@ -0,0 +1,193 @@
+//! Making "fire an N-chain with these pairs" puzzles from a seed.
+
+use std::fmt;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::game::{Board, BitBoard, Goal, Puzzle, BOARD_WIDTH};
+use crate::game::color::Color;
+
+use super::potential::bit_potential;
+use super::solver::{solve, Solutions};
+
+// random boards tried before giving up
+const ATTEMPTS: u32 = 400;
+// pairs dealt into a board while building it up
+const MAX_BUILD_PAIRS: usize = 24;
+// columns are kept this low while building, leaving room to play
+const BUILD_HEIGHT: usize = 9;
+
+/// Build a puzzle asking for a chain of at least `chains` with exactly
+/// `pairs` pairs, the last of them firing it.
+///
+/// The same arguments always give the same puzzle. Only puzzles the solver
+/// finds exactly one solution to are kept, so `None` means none turned up
+/// within a fixed number of tries; long chains with many pairs are the
+/// most likely to miss.
+pub fn generate(seed: u64, chains: u32, pairs: usize) -> Option<Puzzle> {
+    if chains == 0 || pairs == 0 {
+        return None;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..ATTEMPTS).find_map(|_| attempt(&mut rng, chains, pairs))
+}
+
+/// Why `daily` has no puzzle for a date.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DailyError {
+    /// The date isn't on the calendar, like month 13 or February 30.
+    BadDate,
+    /// No puzzle turned up for the day within the generator's tries.
+    NotFound,
+}
+
+impl fmt::Display for DailyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DailyError::BadDate => write!(f, "no such date"),
+            DailyError::NotFound => write!(f, "no puzzle for that day"),
+        }
+    }
+}
+
+impl std::error::Error for DailyError {}
+
+/// The puzzle for a date, the same for everyone who asks that day. They
+/// get harder from Monday through to Sunday.
+pub fn daily(year: u32, month: u32, day: u32) -> Result<Puzzle, DailyError> {
+    if !(1..=12).contains(&month) || day == 0
+            || day > days_in_month(year, month) {
+        return Err(DailyError::BadDate);
+    }
+    let seed = (year as u64) * 10_000 + (month as u64) * 100 + day as u64;
+    let (chains, pairs) = match weekday(year, month, day) {
+        0 | 1 => (2, 1),
+        2 | 3 => (3, 1),
+        4 | 5 => (3, 2),
+        _ => (4, 2),
+    };
+    let mut puzzle = generate(seed, chains, pairs)
+        .ok_or(DailyError::NotFound)?;
+    puzzle.name = format!("Daily puzzle {}-{:02}-{:02}", year, month, day);
+    Ok(puzzle)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// 0 for Monday up to 6 for Sunday, by Sakamoto's method, for a real date
+fn weekday(year: u32, month: u32, day: u32) -> u32 {
+    const OFFSETS: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    // January and February count as the end of the year before; weekdays
+    // repeat every 400 years, so borrow from 400 on instead of going below 0
+    let year = if month < 3 { year + 399 } else { year };
+    let month = month as usize;
+    let sunday_first = year + year / 4 - year / 100 + year / 400
+        + OFFSETS[month - 1] + day;
+    (sunday_first + 6) % 7
+}
+
+fn random_color(rng: &mut StdRng) -> Color {
+    Color::exclude(rng, Color::Violet)
+}
+
+fn random_pair(rng: &mut StdRng) -> (Color, Color) {
+    (random_color(rng), random_color(rng))
+}
+
+fn potential_chains(bits: &BitBoard) -> u32 {
+    bit_potential(bits).map_or(0, |potential| potential.result.chains)
+}
+
+// deal pairs into a board, each where it best builds toward a chain without
+// popping anything, until it is ready to fire one `chains` long; then hand
+// the last `pairs - 1` of them and a trigger to the player
+fn attempt(rng: &mut StdRng, chains: u32, pairs: usize) -> Option<Puzzle> {
+    let mut boards = vec![Board::new()];
+    let mut dealt = Vec::new();
+    let mut potential = None;
+    while dealt.len() < MAX_BUILD_PAIRS {
+        let board = *boards.last().unwrap();
+        let pair = random_pair(rng);
+        let (_, best) = board.legal_placements().iter()
+            .filter_map(|placement| {
+                let mut bits = BitBoard::from(&board);
+                let result = bits.place(
+                    pair, placement.column, placement.orientation).ok()?;
+                let low = (0..BOARD_WIDTH)
+                    .all(|x| bits.column_height(x) <= BUILD_HEIGHT);
+                if result.chains > 0 || !low {
+                    return None;
+                }
+                let value = potential_chains(&bits) as f32 + rng.gen::<f32>();
+                Some((value, bits))
+            })
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())?;
+        boards.push(Board::from(&best));
+        dealt.push(pair);
+        potential = bit_potential(&best);
+        if potential.is_some_and(|p| p.result.chains >= chains) {
+            break;
+        }
+    }
+    let potential = potential.filter(|p| p.result.chains >= chains)?;
+    if dealt.len() < pairs - 1 {
+        return None;
+    }
+
+    let start = dealt.len() - (pairs - 1);
+    let trigger = if potential.count == 2 {
+        (potential.color, potential.color)
+    } else {
+        let other = (0..).map(|_| random_color(rng))
+            .find(|color| *color != potential.color)
+            .unwrap();
+        (potential.color, other)
+    };
+    let mut puzzle_pairs = dealt[start..].to_vec();
+    puzzle_pairs.push(trigger);
+    let puzzle = Puzzle {
+        name: format!("{}-chain in {} pairs", chains, pairs),
+        goal: Goal::Chain(chains),
+        pairs: puzzle_pairs,
+        board: boards[start],
+    };
+    match solve(&puzzle) {
+        Solutions::Unique(solution) if solution.len() == pairs => Some(puzzle),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(weekday(2000, 1, 1), 5);
+        assert_eq!(weekday(2024, 2, 29), 3);
+        assert_eq!(weekday(2026, 10, 18), 6);
+        assert_eq!(weekday(1900, 3, 1), 3);
+        // the first days of year 0 and year 1, which borrow a year
+        assert_eq!(weekday(0, 1, 1), 5);
+        assert_eq!(weekday(1, 1, 1), 0);
+    }
+
+    #[test]
+    fn dates_off_the_calendar_have_no_puzzle() {
+        for (year, month, day) in [(2026, 13, 1), (2026, 0, 1), (2026, 1, 0),
+                                   (2026, 2, 29), (1900, 2, 29), (2026, 4, 31)] {
+            assert_eq!(daily(year, month, day).err(), Some(DailyError::BadDate));
+        }
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2024, 2), 29);
+    }
+}
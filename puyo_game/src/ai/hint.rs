@@ -0,0 +1,85 @@
+use std::fmt;
+
+use crate::game::{Game, Placement};
+use crate::game::color::Color;
+use crate::game::util::Coord;
+
+use super::potential::chain_potential;
+use super::search::{self, DEATH};
+
+/// Why a hint picked its placement, shortest explanation first.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Reason {
+    /// Most other placements top out.
+    PreventsDeath,
+    /// The placement sets off a chain right away.
+    Fires { chains: u32 },
+    /// The chain the board is ready to fire gets longer.
+    Extends { chains: u32 },
+    /// The board wasn't ready to fire anything and now is.
+    Starts { chains: u32 },
+    /// Nothing stands out; it just searched best with the next pair.
+    BestShape,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::PreventsDeath => write!(f, "prevents death"),
+            Reason::Fires { chains } => write!(f, "fires {}-chain", chains),
+            Reason::Extends { chains } => write!(f, "extends {}-chain", chains),
+            Reason::Starts { chains } => write!(f, "sets up {}-chain", chains),
+            Reason::BestShape => write!(f, "keeps a good shape"),
+        }
+    }
+}
+
+/// A suggested placement for the pair under control.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hint {
+    pub placement: Placement,
+    /// Where the pivot and wheel come to rest, for drawing a ghost.
+    pub landing: [(Coord, Color); 2],
+    pub reason: Reason,
+}
+
+/// The best placement for the pair under control, searching through the
+/// next pair as well, or `None` if there is no pair to place.
+pub fn hint(game: &Game) -> Option<Hint> {
+    let current = game.current_pair()?;
+    let pairs = [current, game.next_puyo()];
+    let stack = game.stack();
+    let valued: Vec<(i32, Placement)> = game.legal_placements().into_iter()
+        .map(|placement| (search::value(&stack, &pairs, &placement), placement))
+        .collect();
+    let total = valued.len();
+    let deaths = valued.iter().filter(|(value, _)| doomed(*value)).count();
+    let (best_value, placement) = valued.into_iter()
+        .max_by_key(|(value, _)| *value)?;
+
+    let landing = stack.landing(
+        current, placement.column, placement.orientation).ok()?;
+    let mut after = stack;
+    after.drop_pair(current, placement.column, placement.orientation).ok()?;
+    let result = after.resolve_chains();
+    let before = chain_potential(&stack).map_or(0, |p| p.result.chains);
+    let potential = chain_potential(&after).map_or(0, |p| p.result.chains);
+
+    let reason = if !doomed(best_value) && deaths > 0 && deaths * 2 >= total {
+        Reason::PreventsDeath
+    } else if result.chains > 0 {
+        Reason::Fires { chains: result.chains }
+    } else if potential > before && before > 0 {
+        Reason::Extends { chains: potential }
+    } else if potential > before {
+        Reason::Starts { chains: potential }
+    } else {
+        Reason::BestShape
+    };
+    Some(Hint { placement, landing, reason })
+}
+
+// a chain fired on the way doesn't make up for topping out afterwards
+fn doomed(value: i32) -> bool {
+    value < DEATH / 2
+}
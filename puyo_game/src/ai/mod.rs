@@ -0,0 +1,16 @@
+pub mod search;
+pub mod potential;
+pub mod hint;
+pub mod review;
+pub mod pattern;
+pub mod solver;
+pub mod generator;
+pub mod cpu;
+
+pub use cpu::{Cpu, Difficulty};
+pub use potential::{chain_potential, Potential};
+pub use hint::{hint, Hint, Reason};
+pub use review::{Review, ReviewedMove, Flag};
+pub use pattern::{recognize, PatternMatch, Template};
+pub use solver::{solve, Solution, Solutions};
+pub use generator::{generate, daily, DailyError};
@@ -0,0 +1,243 @@
+//! Recognizing well known chain-building shapes.
+//!
+//! Templates are written like a `Board` layout, top row first and resting
+//! on the bottom row, except that the letters `A` to `E` stand for colors
+//! instead of being colors: cells with the same letter need the same
+//! color and different letters need different colors. `.` can be
+//! anything. A template can sit anywhere along the bottom of the board,
+//! either way round.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::{Board, BOARD_WIDTH, BOARD_HEIGHT};
+use crate::game::color::Color;
+use crate::game::util::Coord;
+
+const PATTERN_COLORS: [Color; 5] = [
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::Yellow,
+    Color::Violet,
+];
+
+pub const GTR: &str = "
+    AB.
+    AAB
+    BBC
+";
+
+pub const NEW_GTR: &str = "
+    AB.
+    AA.
+    BBB
+";
+
+pub const STAIRS: &str = "
+    .BCD
+    .BCD
+    ABCD
+    AABC
+";
+
+pub const SANDWICH: &str = "
+    A.
+    A.
+    B.
+    BB
+    AA
+";
+
+/// Why a text layout could not be read as a `Template`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParseTemplateError {
+    /// A row, counted from the top of the text, was a different width from
+    /// the first.
+    BadRow(usize),
+    BadCell(char),
+    TooBig,
+    Empty,
+}
+
+impl fmt::Display for ParseTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTemplateError::BadRow(row) => write!(
+                f, "row {} is not as wide as the first", row + 1),
+            ParseTemplateError::BadCell(letter) => write!(
+                f, "{:?} is not a color letter", letter),
+            ParseTemplateError::TooBig => write!(
+                f, "bigger than {}x{}", BOARD_WIDTH, BOARD_HEIGHT),
+            ParseTemplateError::Empty => write!(f, "no cells"),
+        }
+    }
+}
+
+impl std::error::Error for ParseTemplateError {}
+
+/// A shape to look for, as (x, y, color letter) cells.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Template {
+    width: usize,
+    cells: Vec<(usize, usize, usize)>,
+    letters: usize,
+}
+
+impl Template {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+impl FromStr for Template {
+    type Err = ParseTemplateError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if rows.len() > BOARD_HEIGHT - 1 || width > BOARD_WIDTH {
+            return Err(ParseTemplateError::TooBig);
+        }
+        let mut cells = Vec::new();
+        let mut letters = 0;
+        for (i, line) in rows.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(ParseTemplateError::BadRow(i));
+            }
+            let y = rows.len() - 1 - i;
+            for (x, letter) in line.chars().enumerate() {
+                match letter {
+                    '.' => (),
+                    'A'..='E' => {
+                        let index = letter as usize - 'A' as usize;
+                        letters = letters.max(index + 1);
+                        cells.push((x, y, index));
+                    }
+                    _ => return Err(ParseTemplateError::BadCell(letter)),
+                }
+            }
+        }
+        if cells.is_empty() {
+            return Err(ParseTemplateError::Empty);
+        }
+        Ok(Template { width, cells, letters })
+    }
+}
+
+/// The standard shapes, by name.
+pub fn standard_templates() -> Vec<(&'static str, Template)> {
+    [("GTR", GTR), ("New GTR", NEW_GTR), ("Stairs", STAIRS),
+     ("Sandwich", SANDWICH)]
+        .iter()
+        .map(|(name, text)| (*name, text.parse().unwrap()))
+        .collect()
+}
+
+/// How closely some part of a board follows a template.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PatternMatch {
+    /// From 0 to 1: the share of the template's cells already in place,
+    /// less one for each cell holding the wrong color.
+    pub similarity: f32,
+    /// Column of the template's left edge, after mirroring.
+    pub offset: usize,
+    pub mirrored: bool,
+    /// The color each template letter stands for.
+    pub colors: Vec<Color>,
+    /// Empty cells the template still needs filled, and with what.
+    pub missing: Vec<(Coord, Color)>,
+    /// Cells holding something the template doesn't want there.
+    pub wrong: Vec<Coord>,
+}
+
+/// The closest `template` comes to matching `board` anywhere along its
+/// bottom, either way round.
+pub fn match_template(board: &Board, template: &Template) -> PatternMatch {
+    let mut best: Option<(i32, PatternMatch)> = None;
+    for mirrored in [false, true].iter() {
+        for offset in 0..=(BOARD_WIDTH - template.width) {
+            let coords: Vec<(Coord, usize)> = template.cells.iter()
+                .map(|(x, y, letter)| {
+                    let x = if *mirrored { template.width - 1 - x } else { *x };
+                    (Coord { x: (offset + x) as i32, y: *y as i32 }, *letter)
+                })
+                .collect();
+            for colors in assignments(template.letters) {
+                let (score, found) = score(board, &coords, &colors);
+                if best.as_ref().map_or(true, |(best, _)| score > *best) {
+                    let mut found = found;
+                    found.offset = offset;
+                    found.mirrored = *mirrored;
+                    best = Some((score, found));
+                }
+            }
+        }
+    }
+    best.unwrap().1
+}
+
+/// How closely `board` follows each standard shape, closest first.
+pub fn recognize(board: &Board) -> Vec<(&'static str, PatternMatch)> {
+    let mut matches: Vec<_> = standard_templates().into_iter()
+        .map(|(name, template)| (name, match_template(board, &template)))
+        .collect();
+    matches.sort_by(|a, b| b.1.similarity.partial_cmp(&a.1.similarity)
+                    .unwrap());
+    matches
+}
+
+fn score(board: &Board, coords: &[(Coord, usize)], colors: &[Color])
+        -> (i32, PatternMatch) {
+    let mut found = PatternMatch {
+        similarity: 0.0,
+        offset: 0,
+        mirrored: false,
+        colors: colors.to_vec(),
+        missing: Vec::new(),
+        wrong: Vec::new(),
+    };
+    let mut placed = 0;
+    for (coord, letter) in coords {
+        let wanted = colors[*letter];
+        match board.get(coord) {
+            Some(color) if color == wanted => placed += 1,
+            Some(Color::Empty) => found.missing.push((*coord, wanted)),
+            _ => found.wrong.push(*coord),
+        }
+    }
+    let score = placed - found.wrong.len() as i32;
+    found.similarity = score.max(0) as f32 / coords.len() as f32;
+    (score, found)
+}
+
+// every way of giving `letters` letters different colors
+fn assignments(letters: usize) -> Vec<Vec<Color>> {
+    let mut all = vec![Vec::new()];
+    for _ in 0..letters {
+        all = all.into_iter()
+            .flat_map(|colors: Vec<Color>| {
+                PATTERN_COLORS.iter()
+                    .filter(|color| !colors.contains(color))
+                    .map(|color| {
+                        let mut more = colors.clone();
+                        more.push(*color);
+                        more
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+    all
+}
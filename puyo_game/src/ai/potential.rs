@@ -0,0 +1,71 @@
+use crate::game::{Board, BitBoard, ChainResult, BOARD_WIDTH, BOARD_HEIGHT};
+use crate::game::color::Color;
+use crate::game::util::Coord;
+
+const TRIGGER_COLORS: [Color; 5] = [
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::Yellow,
+    Color::Violet,
+];
+const MAX_TRIGGER: usize = 2;
+
+/// The biggest chain a board is ready to fire, and how to set it off.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Potential {
+    /// Where the first trigger puyo lands.
+    pub trigger: Coord,
+    pub color: Color,
+    /// How many puyo of `color` are dropped into the trigger's column.
+    pub count: usize,
+    pub result: ChainResult,
+}
+
+/// The longest chain that dropping one or two puyo of one color into one
+/// column sets off, or `None` if nothing would pop.
+///
+/// Ties go to the higher score, then to the smaller trigger.
+pub fn chain_potential(board: &Board) -> Option<Potential> {
+    bit_potential(&BitBoard::from(board))
+}
+
+/// Same as `chain_potential`, for search code already using `BitBoard`.
+pub fn bit_potential(bits: &BitBoard) -> Option<Potential> {
+    let mut best: Option<Potential> = None;
+    for x in 0..BOARD_WIDTH {
+        let height = bits.column_height(x);
+        for color in TRIGGER_COLORS.iter() {
+            for count in 1..=MAX_TRIGGER {
+                // triggers have to land in the visible rows to pop
+                if height + count > BOARD_HEIGHT - 1 {
+                    break;
+                }
+                let mut trial = *bits;
+                for y in height..height + count {
+                    trial.set(&Coord { x: x as i32, y: y as i32 }, *color);
+                }
+                let result = trial.resolve_chains();
+                if result.chains == 0 {
+                    continue;
+                }
+                let better = best.map_or(true, |best| {
+                    (result.chains, result.score, MAX_TRIGGER - count)
+                        > (best.result.chains, best.result.score,
+                           MAX_TRIGGER - best.count)
+                });
+                if better {
+                    best = Some(Potential {
+                        trigger: Coord { x: x as i32, y: height as i32 },
+                        color: *color,
+                        count,
+                        result,
+                    });
+                }
+                // a single puyo that already pops is the better trigger
+                break;
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,216 @@
+use crate::game::{Board, BitBoard, Direction, Game, Input, Placement, Versus};
+use crate::game::color::Color;
+use crate::game::controller::Replay;
+use crate::game::util::Coord;
+
+use super::potential::chain_potential;
+use super::search;
+
+// how many links of chain potential a quiet placement can throw away
+// before it is worth pointing out
+const POTENTIAL_DROP: u32 = 2;
+// a counter has to send back at least this much, or all that is coming
+const COUNTER_GARBAGE: u32 = 6;
+
+/// Something a reviewed placement did worse than it could have.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Flag {
+    /// Chain potential fell from `before` to `after` without firing.
+    PotentialDrop { before: u32, after: u32 },
+    /// Garbage was on its way and another placement could have sent
+    /// `garbage` back, but the one played sent nothing.
+    MissedCounter { pending: u32, garbage: u32 },
+}
+
+/// One placement from the game, next to the one the search liked best.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReviewedMove {
+    pub pair_number: u32,
+    pub frame: u32,
+    /// The board the pair was placed on.
+    pub stack: Board,
+    pub pair: (Color, Color),
+    pub next: (Color, Color),
+    pub pending_garbage: u32,
+    pub played: (usize, Direction),
+    pub played_landing: [(Coord, Color); 2],
+    pub played_value: i32,
+    pub best: (usize, Direction),
+    pub best_landing: [(Coord, Color); 2],
+    pub best_value: i32,
+    pub flags: Vec<Flag>,
+}
+
+impl ReviewedMove {
+    pub fn is_best(&self) -> bool {
+        self.played_value >= self.best_value
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Review {
+    pub moves: Vec<ReviewedMove>,
+}
+
+impl Review {
+    /// The placements with something worth pointing out.
+    pub fn flagged(&self) -> impl Iterator<Item = &ReviewedMove> {
+        self.moves.iter().filter(|reviewed| !reviewed.flags.is_empty())
+    }
+}
+
+struct Snapshot {
+    pair_number: u32,
+    frame: u32,
+    stack: Board,
+    pair: (Color, Color),
+    next: (Color, Color),
+    pending_garbage: u32,
+}
+
+/// Watches a game tick by tick and reviews each pair as it locks.
+#[derive(Default)]
+pub struct Reviewer {
+    snapshot: Option<Snapshot>,
+    review: Review,
+}
+
+impl Reviewer {
+    pub fn new() -> Reviewer {
+        Reviewer::default()
+    }
+
+    pub fn before_tick(&mut self, game: &Game) {
+        if let Some(pair) = game.current_pair() {
+            self.snapshot = Some(Snapshot {
+                pair_number: game.pair_number(),
+                frame: game.frame(),
+                stack: game.stack(),
+                pair,
+                next: game.next_puyo(),
+                pending_garbage: game.pending_garbage(),
+            });
+        }
+    }
+
+    pub fn after_tick(&mut self, game: &Game) {
+        let locked = match &self.snapshot {
+            Some(snapshot) => {
+                game.current_pair().is_none()
+                    || game.pair_number() != snapshot.pair_number
+            }
+            None => false,
+        };
+        if !locked {
+            return;
+        }
+        let snapshot = self.snapshot.take().unwrap();
+        if let Some(played) = game.last_placement() {
+            if let Some(reviewed) = review_move(snapshot, played) {
+                self.review.moves.push(reviewed);
+            }
+        }
+    }
+
+    pub fn finish(self) -> Review {
+        self.review
+    }
+}
+
+fn review_move(snapshot: Snapshot, played: (usize, Direction))
+        -> Option<ReviewedMove> {
+    let pairs = [snapshot.pair, snapshot.next];
+    let stack = &snapshot.stack;
+    let placements = stack.legal_placements();
+    let value = |placement: &Placement| search::value(stack, &pairs, placement);
+    let (best_value, best) = placements.iter()
+        .map(|placement| (value(placement), placement))
+        .max_by_key(|(value, _)| *value)?;
+    let played_value = placements.iter()
+        .find(|placement| (placement.column, placement.orientation) == played)
+        .map_or(search::DEATH, value);
+
+    let fire = |(column, orientation): (usize, Direction)| {
+        BitBoard::from(stack).place(snapshot.pair, column, orientation).ok()
+    };
+    let played_result = fire(played).unwrap_or_default();
+    let mut flags = Vec::new();
+
+    let potential = |board: &Board| {
+        chain_potential(board).map_or(0, |potential| potential.result.chains)
+    };
+    if played_result.chains == 0 {
+        let mut after = *stack;
+        if after.drop_pair(snapshot.pair, played.0, played.1).is_ok() {
+            let before = potential(stack);
+            let after = potential(&after);
+            if after + POTENTIAL_DROP <= before {
+                flags.push(Flag::PotentialDrop { before, after });
+            }
+        }
+    }
+
+    if snapshot.pending_garbage > 0 && played_result.garbage == 0 {
+        let counter = placements.iter()
+            .filter_map(|placement| {
+                fire((placement.column, placement.orientation))
+            })
+            .map(|result| result.garbage)
+            .max()
+            .unwrap_or(0);
+        if counter >= COUNTER_GARBAGE.min(snapshot.pending_garbage) {
+            flags.push(Flag::MissedCounter {
+                pending: snapshot.pending_garbage,
+                garbage: counter,
+            });
+        }
+    }
+
+    Some(ReviewedMove {
+        pair_number: snapshot.pair_number,
+        frame: snapshot.frame,
+        stack: snapshot.stack,
+        pair: snapshot.pair,
+        next: snapshot.next,
+        pending_garbage: snapshot.pending_garbage,
+        played,
+        played_landing: stack.landing(snapshot.pair, played.0, played.1).ok()?,
+        played_value,
+        best: (best.column, best.orientation),
+        best_landing: stack.landing(
+            snapshot.pair, best.column, best.orientation).ok()?,
+        best_value,
+        flags,
+    })
+}
+
+/// Replay a game played alone from `seed` with `inputs`, for `frames`
+/// ticks or until it is over, and review every placement.
+pub fn review_game(seed: u64, inputs: Vec<(u32, Input)>, frames: u32)
+        -> Review {
+    let mut game = Game::with_seed(seed);
+    let mut replay = Replay::new(inputs);
+    let mut reviewer = Reviewer::new();
+    while !game.is_over() && game.frame() < frames {
+        reviewer.before_tick(&game);
+        game.tick_with(&mut replay);
+        reviewer.after_tick(&game);
+    }
+    reviewer.finish()
+}
+
+/// Replay a match from its seed and both players' recordings, and review
+/// `player`'s placements.
+pub fn review_versus(seed: u64, inputs: [Vec<(u32, Input)>; 2],
+                     player: usize, frames: u32) -> Review {
+    let [one, two] = inputs;
+    let mut versus = Versus::with_seed(
+        seed, Box::new(Replay::new(one)), Box::new(Replay::new(two)));
+    let mut reviewer = Reviewer::new();
+    while versus.outcome().is_none() && versus.game(player).frame() < frames {
+        reviewer.before_tick(versus.game(player));
+        versus.tick();
+        reviewer.after_tick(versus.game(player));
+    }
+    reviewer.finish()
+}
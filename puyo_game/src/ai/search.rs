@@ -0,0 +1,81 @@
+use crate::game::{Board, BitBoard, Placement, BOARD_WIDTH};
+use crate::game::color::Color;
+
+use super::potential;
+
+/// The value of a position the game is lost in.
+pub const DEATH: i32 = i32::MIN / 2;
+
+// columns taller than this start to count against a position
+const SAFE_HEIGHT: usize = 9;
+const HEIGHT_PENALTY: i32 = 40;
+const CONNECTION_BONUS: [i32; 4] = [0, 0, 4, 10];
+// per link squared of the chain the board is ready to fire
+const POTENTIAL_BONUS: i32 = 20;
+
+/// How promising a settled board looks, not counting anything it has
+/// already fired.
+pub fn evaluate(bits: &BitBoard) -> i32 {
+    if bits.spawn_blocked() {
+        return DEATH;
+    }
+    let connections: i32 = bits.groups().iter()
+        .map(|(_, group)| group.count_ones() as usize)
+        .map(|size| CONNECTION_BONUS[size.min(3)])
+        .sum();
+    let height: i32 = (0..BOARD_WIDTH)
+        .map(|x| bits.column_height(x).saturating_sub(SAFE_HEIGHT) as i32)
+        .sum();
+    let potential = potential::bit_potential(bits)
+        .map_or(0, |potential| potential.result.chains as i32);
+    connections + potential * potential * POTENTIAL_BONUS
+        - height * HEIGHT_PENALTY
+}
+
+/// The value of putting `pairs[0]` at `placement`, then making the best
+/// possible placement for each of the rest of `pairs` in turn.
+pub fn value(board: &Board, pairs: &[(Color, Color)], placement: &Placement)
+        -> i32 {
+    let mut bits = BitBoard::from(board);
+    let result = match bits.place(
+            pairs[0], placement.column, placement.orientation) {
+        Ok(result) => result,
+        Err(_) => return DEATH,
+    };
+    if bits.spawn_blocked() {
+        return DEATH;
+    }
+    let fired = fired_value(result.score, result.chains);
+    let rest = if pairs.len() > 1 {
+        best_value(&Board::from(&bits), &pairs[1..])
+    } else {
+        evaluate(&bits)
+    };
+    fired.saturating_add(rest)
+}
+
+// longer chains are worth disproportionately more than quick pops
+fn fired_value(score: u32, chains: u32) -> i32 {
+    score.saturating_mul(chains).min(i32::MAX as u32) as i32
+}
+
+/// The value of the best placement for `pairs[0]` on `board`, looking
+/// ahead through the rest of `pairs`.
+pub fn best_value(board: &Board, pairs: &[(Color, Color)]) -> i32 {
+    board.legal_placements().iter()
+        .map(|placement| value(board, pairs, placement))
+        .max()
+        .unwrap_or(DEATH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_chains_stay_positive() {
+        assert_eq!(fired_value(1_000, 3), 3_000);
+        assert_eq!(fired_value(200_000_000, 19), i32::MAX);
+        assert_eq!(fired_value(u32::MAX, u32::MAX), i32::MAX);
+    }
+}
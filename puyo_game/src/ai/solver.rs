@@ -0,0 +1,157 @@
+//! Exhaustive search for the ways to solve a puzzle.
+
+use std::cmp;
+use std::collections::HashSet;
+
+use crate::game::{Board, Direction, Goal, Puzzle};
+use crate::game::chain::ChainTracker;
+use crate::game::color::Color;
+
+/// The placements to make, in order, one per pair.
+pub type Solution = Vec<(usize, Direction)>;
+
+/// How many ways a puzzle can be solved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Solutions {
+    Unsolvable,
+    Unique(Solution),
+    /// Two of the ways, for showing why the puzzle isn't unique.
+    Many(Solution, Solution),
+}
+
+// a board part way through a puzzle, with the tallies the goal looks at
+#[derive(Clone)]
+struct State {
+    board: Board,
+    chain: ChainTracker,
+    max_chain: u32,
+}
+
+impl State {
+    // play out gravity and chains the way a game does between pairs
+    fn resolve(&mut self) {
+        loop {
+            self.board.settle();
+            if !self.board.pop_groups(&mut self.chain) {
+                break;
+            }
+        }
+        self.max_chain = cmp::max(self.max_chain, self.chain.chains());
+        self.chain.convert_to_garbage();
+    }
+
+    fn is_solved(&self, goal: &Goal) -> bool {
+        goal.is_met_by(&self.board, self.max_chain, |c| self.chain.popped(c))
+    }
+
+    // everything about this state the rest of the search depends on
+    fn key(&self, goal: &Goal, pairs_used: usize) -> (Board, usize, u32) {
+        let progress = match goal {
+            Goal::Chain(_) => self.max_chain,
+            Goal::Pop { color, .. } => self.chain.popped(*color),
+            Goal::ClearAll | Goal::ClearGarbage => 0,
+        };
+        (self.board, pairs_used, progress)
+    }
+}
+
+struct Search<'a> {
+    goal: &'a Goal,
+    pairs: &'a [(Color, Color)],
+    limit: usize,
+    found: Vec<Solution>,
+    // states already known to lead nowhere
+    dead: HashSet<(Board, usize, u32)>,
+}
+
+impl<'a> Search<'a> {
+    fn run(&mut self, state: &State, placed: &mut Solution) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if state.is_solved(self.goal) {
+            self.found.push(placed.clone());
+            return;
+        }
+        let used = placed.len();
+        let pair = match self.pairs.get(used) {
+            Some(pair) if !state.board.spawn_blocked() => *pair,
+            _ => return,
+        };
+        let key = state.key(self.goal, used);
+        if self.dead.contains(&key) {
+            return;
+        }
+        let before = self.found.len();
+        // placements that drop into the same board are the same move
+        let mut tried = HashSet::new();
+        for placement in state.board.legal_placements() {
+            let mut next = state.clone();
+            let orientation = placement.orientation;
+            if next.board.drop_pair(pair, placement.column, orientation)
+                    .is_err() || !tried.insert(next.board) {
+                continue;
+            }
+            next.resolve();
+            placed.push((placement.column, orientation));
+            self.run(&next, placed);
+            placed.pop();
+            if self.found.len() >= self.limit {
+                return;
+            }
+        }
+        if self.found.len() == before {
+            self.dead.insert(key);
+        }
+    }
+}
+
+/// Up to `limit` different ways to solve `puzzle`.
+///
+/// Placements that leave the same board behind only count once, and a
+/// solution stops at the pair that meets the goal.
+pub fn solutions(puzzle: &Puzzle, limit: usize) -> Vec<Solution> {
+    let mut state = State {
+        board: puzzle.board,
+        chain: ChainTracker::new(),
+        max_chain: 0,
+    };
+    state.resolve();
+    let mut search = Search {
+        goal: &puzzle.goal,
+        pairs: &puzzle.pairs,
+        limit,
+        found: Vec::new(),
+        dead: HashSet::new(),
+    };
+    search.run(&state, &mut Vec::new());
+    search.found
+}
+
+/// Whether `puzzle` has no solution, exactly one, or more than one.
+pub fn solve(puzzle: &Puzzle) -> Solutions {
+    let mut found = solutions(puzzle, 2).into_iter();
+    match (found.next(), found.next()) {
+        (None, _) => Solutions::Unsolvable,
+        (Some(only), None) => Solutions::Unique(only),
+        (Some(one), Some(two)) => Solutions::Many(one, two),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_have_one_solution() {
+        // the puzzle screen's puzzles are the text between backticks
+        let source = include_str!("../../../www/puzzles.js");
+        let puzzles: Vec<&str> = source.split('`').skip(1).step_by(2).collect();
+        assert!(!puzzles.is_empty());
+        for text in puzzles {
+            let puzzle: Puzzle = text.parse().unwrap();
+            assert!(matches!(solve(&puzzle), Solutions::Unique(_)),
+                    "{} doesn't have exactly one solution", puzzle.name);
+        }
+    }
+}
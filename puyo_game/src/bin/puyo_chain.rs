@@ -0,0 +1,64 @@
+//! Show a chain one link at a time: what fell, what popped and how it
+//! scored.
+//!
+//! usage: puyo_chain [FILE | FIELD_CODE]
+//!
+//! The board is a text layout like `Board` reads or a simulator field
+//! code, from the file, the argument itself, or standard input.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use puyo_game::game::{Board, ChainSteps, FieldCode};
+
+fn read_board(text: &str) -> Option<Board> {
+    text.parse::<Board>().ok()
+        .or_else(|| text.parse::<FieldCode>().ok().map(|code| code.board))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let text = match &args[..] {
+        [] => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).unwrap_or_else(|err| {
+                eprintln!("could not read the board: {}", err);
+                process::exit(1);
+            });
+            text
+        }
+        [arg] => fs::read_to_string(arg).unwrap_or_else(|_| arg.clone()),
+        _ => {
+            eprintln!("usage: puyo_chain [FILE | FIELD_CODE]");
+            process::exit(2);
+        }
+    };
+    let board = read_board(&text).unwrap_or_else(|| {
+        eprintln!("not a board layout or a field code");
+        process::exit(1);
+    });
+
+    let mut steps = ChainSteps::new(board);
+    for step in steps.by_ref() {
+        println!("== link {} ==", step.number);
+        if step.fell {
+            println!("after falling:");
+            println!("{}", step.settled);
+        }
+        for (color, cells) in step.groups.iter() {
+            println!("popped {} {:?}", cells.len(), color);
+        }
+        if !step.garbage.is_empty() {
+            println!("cleared {} garbage", step.garbage.len());
+        }
+        println!("score so far: {}", step.breakdown);
+        println!("{}", step.after);
+    }
+    let (end, result) = steps.finish();
+    println!("== done ==");
+    println!("{}", end);
+    println!("{} chain, {} cleared, {} points, {} garbage sent",
+             result.chains, result.cleared, result.score, result.garbage);
+}
@@ -0,0 +1,108 @@
+//! Run one or two external bots, see `puyo_game::bot` for the protocol.
+//!
+//! usage: puyo_host [--time-limit MS] [--max-frames N] BOT [BOT]
+//!
+//! Each BOT is a command line, quoted as one argument. With one bot it
+//! plays alone until it tops out; with two they play each other.
+
+use std::env;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use puyo_game::bot::{ExternalBot, Faults};
+use puyo_game::game::{Controller, Game, Outcome, Versus};
+
+struct Options {
+    time_limit: Duration,
+    max_frames: u32,
+    bots: Vec<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: puyo_host [--time-limit MS] [--max-frames N] BOT [BOT]");
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        time_limit: Duration::from_millis(1000),
+        max_frames: 100_000,
+        bots: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time-limit" => {
+                let ms = args.next().and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage());
+                options.time_limit = Duration::from_millis(ms);
+            }
+            "--max-frames" => {
+                options.max_frames = args.next().and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            _ => options.bots.push(arg),
+        }
+    }
+    if options.bots.is_empty() || options.bots.len() > 2 {
+        usage();
+    }
+    options
+}
+
+fn spawn(command: &str, time_limit: Duration)
+        -> (ExternalBot, Arc<Mutex<Faults>>) {
+    match ExternalBot::spawn(command, time_limit) {
+        Ok(bot) => {
+            let faults = bot.faults();
+            (bot, faults)
+        }
+        Err(err) => {
+            eprintln!("could not start {:?}: {}", command, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn report(name: &str, game: &Game, faults: Faults) {
+    println!("{}: score {}, {} pairs, {} timeouts, {} bad replies, \
+              {} unreachable moves",
+             name, game.score(), game.pair_number(), faults.timeouts,
+             faults.bad_replies, faults.unreachable);
+}
+
+fn main() {
+    let options = parse_args();
+    if options.bots.len() == 1 {
+        let name = &options.bots[0];
+        let (mut bot, faults) = spawn(name, options.time_limit);
+        let mut game = Game::new();
+        while !game.is_over() && game.frame() < options.max_frames {
+            game.tick_with(&mut bot);
+        }
+        // one last poll so the bot sees the game is over
+        bot.poll(&game);
+        report(name, &game, *faults.lock().unwrap());
+        return;
+    }
+
+    let (one, one_faults) = spawn(&options.bots[0], options.time_limit);
+    let (two, two_faults) = spawn(&options.bots[1], options.time_limit);
+    let mut versus = Versus::new(Box::new(one), Box::new(two));
+    let mut outcome = None;
+    while outcome.is_none() && versus.game(0).frame() < options.max_frames {
+        outcome = versus.tick();
+    }
+    for (player, faults) in [one_faults, two_faults].iter().enumerate() {
+        let faults = *faults.lock().unwrap();
+        report(&options.bots[player], versus.game(player), faults);
+    }
+    match outcome {
+        Some(Outcome::Winner(player)) => {
+            println!("winner: {}", options.bots[player]);
+        }
+        Some(Outcome::Draw) => println!("draw"),
+        None => println!("no result after {} frames", options.max_frames),
+    }
+}
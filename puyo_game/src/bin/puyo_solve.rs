@@ -0,0 +1,64 @@
+//! Check that puzzles can be solved, and in only one way.
+//!
+//! usage: puyo_solve PUZZLE_FILE...
+//!
+//! Each file holds one puzzle in the text format `Puzzle` reads. Exits
+//! with 1 if any puzzle can't be read or doesn't have exactly one
+//! solution.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use puyo_game::ai::{solve, Solution, Solutions};
+use puyo_game::game::Puzzle;
+
+fn describe(solution: &Solution) -> String {
+    let placements: Vec<String> = solution.iter()
+        .map(|(column, orientation)| format!("{} {:?}", column, orientation))
+        .collect();
+    placements.join(", ")
+}
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: puyo_solve PUZZLE_FILE...");
+        process::exit(2);
+    }
+    let mut all_unique = true;
+    for path in paths {
+        let puzzle = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                text.parse::<Puzzle>().map_err(|err| err.to_string())
+            });
+        let puzzle = match puzzle {
+            Ok(puzzle) => puzzle,
+            Err(err) => {
+                println!("{}: {}", path, err);
+                all_unique = false;
+                continue;
+            }
+        };
+        let name = if puzzle.name.is_empty() { &path } else { &puzzle.name };
+        match solve(&puzzle) {
+            Solutions::Unsolvable => {
+                println!("{}: no solution", name);
+                all_unique = false;
+            }
+            Solutions::Unique(solution) => {
+                println!("{}: unique: {}", name, describe(&solution));
+            }
+            Solutions::Many(one, two) => {
+                println!("{}: more than one solution", name);
+                println!("    {}", describe(&one));
+                println!("    {}", describe(&two));
+                all_unique = false;
+            }
+        }
+    }
+    if !all_unique {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,93 @@
+//! Play every entrant against every other one and print the standings.
+//!
+//! usage: puyo_tournament [--games N] [--seed S] [--max-frames N]
+//!                        [--time-limit MS] [--quiet] ENTRANT ENTRANT...
+//!
+//! An ENTRANT is `cpu:easy`, `cpu:normal`, `cpu:hard`, or the command line
+//! of an external bot quoted as one argument.
+
+use std::env;
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+
+use puyo_game::game::Outcome;
+use puyo_game::tournament::{Entrant, Tournament};
+
+fn usage() -> ! {
+    eprintln!("usage: puyo_tournament [--games N] [--seed S] \
+               [--max-frames N] [--time-limit MS] [--quiet] \
+               ENTRANT ENTRANT...");
+    process::exit(2);
+}
+
+fn number<T: FromStr>(value: Option<String>) -> T {
+    value.and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let mut tournament = Tournament::new(Vec::new());
+    let mut quiet = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => tournament.games = number(args.next()),
+            "--seed" => tournament.seed = number(args.next()),
+            "--max-frames" => tournament.max_frames = number(args.next()),
+            "--time-limit" => {
+                tournament.time_limit =
+                    Duration::from_millis(number(args.next()));
+            }
+            "--quiet" => quiet = true,
+            _ => match arg.parse::<Entrant>() {
+                Ok(entrant) => tournament.entrants.push(entrant),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    usage();
+                }
+            },
+        }
+    }
+    if tournament.entrants.len() < 2 {
+        usage();
+    }
+
+    let entrants = tournament.entrants.clone();
+    let standings = tournament.run(|sides, result| {
+        if quiet {
+            return;
+        }
+        let verdict = match result.outcome {
+            Some(Outcome::Winner(side)) => format!("{} wins", entrants[sides[side]]),
+            Some(Outcome::Draw) => "draw".to_string(),
+            None => "unfinished".to_string(),
+        };
+        println!("{} vs {}: {} after {} frames, chains {}-{}, garbage {}-{}",
+                 entrants[sides[0]], entrants[sides[1]], verdict,
+                 result.frames, result.max_chains[0], result.max_chains[1],
+                 result.garbage_sent[0], result.garbage_sent[1]);
+    });
+    let standings = match standings {
+        Ok(standings) => standings,
+        Err(err) => {
+            eprintln!("could not start a bot: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut order: Vec<usize> = (0..entrants.len()).collect();
+    order.sort_by(|a, b| {
+        standings[*b].rating.partial_cmp(&standings[*a].rating).unwrap()
+    });
+    println!();
+    println!("{:>6} {:>6} {:>5} {:>5} {:>5} {:>6} {:>8}  entrant",
+             "rating", "win%", "won", "lost", "drawn", "chain", "garbage");
+    for index in order {
+        let standing = &standings[index];
+        println!("{:>6.0} {:>5.1}% {:>5} {:>5} {:>5} {:>6.2} {:>8}  {}",
+                 standing.rating, standing.win_rate() * 100.0,
+                 standing.wins, standing.losses, standing.draws,
+                 standing.average_max_chain(), standing.garbage_sent,
+                 entrants[index]);
+    }
+}
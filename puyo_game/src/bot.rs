@@ -0,0 +1,311 @@
+//! A line-based JSON protocol for bots that run as separate programs.
+//!
+//! The host starts the bot and writes one JSON object per line to its
+//! stdin whenever a new pair needs placing:
+//!
+//! ```text
+//! {"type":"turn","turn":3,"board":["......", ...],"current":"RG",
+//!  "queue":["BY"],"pending_garbage":0,"time_limit_ms":1000}
+//! ```
+//!
+//! `board` has one string per row, top row first, using the letters from
+//! `Color::to_char`. Pairs are written pivot first, then wheel, and the
+//! pair starts with the wheel above the pivot. The bot answers on its
+//! stdout with one line:
+//!
+//! ```text
+//! {"turn":3,"column":2,"orientation":"up"}
+//! ```
+//!
+//! where `turn` repeats the turn being answered, `column` is where the
+//! pivot goes, counted from 0 on the left, and `orientation` is the side of
+//! the pivot the wheel ends up on: `up`, `right`, `down` or `left`. Answers
+//! for any other turn are thrown away. Answers that are late, unreadable or
+//! not reachable are ignored and the pair is dropped where it spawned. When
+//! the game ends the host closes the bot's stdin.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Board, Controller, Direction, Game, Input};
+use crate::game::color::pair_to_string;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Turn(Turn),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Turn {
+    pub turn: u32,
+    pub board: Vec<String>,
+    pub current: String,
+    pub queue: Vec<String>,
+    pub pending_garbage: u32,
+    pub time_limit_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Orientation {
+    pub fn from_direction(direction: Direction) -> Option<Orientation> {
+        match direction {
+            Direction::Up => Some(Orientation::Up),
+            Direction::Right => Some(Orientation::Right),
+            Direction::Down => Some(Orientation::Down),
+            Direction::Left => Some(Orientation::Left),
+            Direction::None => None,
+        }
+    }
+}
+
+impl From<Orientation> for Direction {
+    fn from(orientation: Orientation) -> Direction {
+        match orientation {
+            Orientation::Up => Direction::Up,
+            Orientation::Right => Direction::Right,
+            Orientation::Down => Direction::Down,
+            Orientation::Left => Direction::Left,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Move {
+    pub turn: u32,
+    pub column: usize,
+    pub orientation: Orientation,
+}
+
+impl Turn {
+    /// Describe the pair `game` has under control, if there is one.
+    pub fn new(game: &Game, time_limit: Duration) -> Option<Turn> {
+        let current = game.current_pair()?;
+        Some(Turn {
+            turn: game.pair_number(),
+            board: game.stack().to_string().lines().map(String::from).collect(),
+            current: pair_to_string(current),
+            queue: vec![pair_to_string(game.next_puyo())],
+            pending_garbage: game.pending_garbage(),
+            time_limit_ms: time_limit.as_millis() as u64,
+        })
+    }
+
+    pub fn board(&self) -> Option<Board> {
+        self.board.join("\n").parse().ok()
+    }
+}
+
+/// Ways an external bot can fail to make a move.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Faults {
+    pub timeouts: u32,
+    pub bad_replies: u32,
+    pub unreachable: u32,
+}
+
+/// A `Controller` backed by a bot program speaking the protocol above.
+pub struct ExternalBot {
+    name: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    replies: Receiver<String>,
+    time_limit: Duration,
+    planned_for: Option<u32>,
+    plan: VecDeque<Input>,
+    faults: Arc<Mutex<Faults>>,
+}
+
+impl ExternalBot {
+    /// Start `command`, a program followed by its arguments separated by
+    /// whitespace.
+    pub fn spawn(command: &str, time_limit: Duration) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput, "empty bot command"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(ExternalBot {
+            name: command.to_string(),
+            child,
+            stdin,
+            replies,
+            time_limit,
+            planned_for: None,
+            plan: VecDeque::new(),
+            faults: Arc::default(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A live view of this bot's fault counts, which stays readable after
+    /// the bot has been handed to a `Versus`.
+    pub fn faults(&self) -> Arc<Mutex<Faults>> {
+        Arc::clone(&self.faults)
+    }
+
+    fn fault(&self, count: impl FnOnce(&mut Faults)) {
+        count(&mut self.faults.lock().unwrap());
+    }
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        let stdin = self.stdin.as_mut().ok_or_else(|| io::Error::new(
+            io::ErrorKind::BrokenPipe, "bot input already closed"))?;
+        let line = serde_json::to_string(message)?;
+        writeln!(stdin, "{}", line)?;
+        stdin.flush()
+    }
+
+    fn ask(&mut self, game: &Game) -> Vec<Input> {
+        let turn = match Turn::new(game, self.time_limit) {
+            Some(turn) => turn,
+            None => return Vec::new(),
+        };
+        let number = turn.turn;
+        // anything still waiting is a late answer to an earlier turn
+        while self.replies.try_recv().is_ok() {}
+        if self.send(&Message::Turn(turn)).is_err() {
+            self.fault(|f| f.bad_replies += 1);
+            return Vec::new();
+        }
+        let deadline = Instant::now() + self.time_limit;
+        let chosen = loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let reply = match self.replies.recv_timeout(left) {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.fault(|f| f.timeouts += 1);
+                    return Vec::new();
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.fault(|f| f.bad_replies += 1);
+                    return Vec::new();
+                }
+            };
+            match serde_json::from_str::<Move>(&reply) {
+                // a late answer to an earlier turn
+                Ok(chosen) if chosen.turn != number => continue,
+                Ok(chosen) => break chosen,
+                Err(_) => {
+                    self.fault(|f| f.bad_replies += 1);
+                    return Vec::new();
+                }
+            }
+        };
+        let orientation = Direction::from(chosen.orientation);
+        match game.legal_placements().into_iter().find(|placement| {
+            placement.column == chosen.column
+                && placement.orientation == orientation
+        }) {
+            Some(placement) => placement.inputs,
+            None => {
+                self.fault(|f| f.unreachable += 1);
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl Controller for ExternalBot {
+    fn poll(&mut self, game: &Game) -> Vec<Input> {
+        if game.is_over() {
+            // closing stdin lets the bot know it is done
+            self.stdin = None;
+            return Vec::new();
+        }
+        if game.current_pair().is_none() {
+            return Vec::new();
+        }
+        if self.planned_for != Some(game.pair_number()) {
+            self.planned_for = Some(game.pair_number());
+            self.plan = self.ask(game).into();
+        }
+        vec![self.plan.pop_front().unwrap_or(Input::Down)]
+    }
+
+    fn reset(&mut self) {
+        self.planned_for = None;
+        self.plan.clear();
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_send<T: Send>() {}
+
+    #[test]
+    fn bots_can_move_between_threads() {
+        is_send::<ExternalBot>();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn answers_for_other_turns_are_thrown_away() {
+        // answers an old turn first, then the one it was asked
+        let script = std::env::temp_dir()
+            .join(format!("puyo_stale_bot_{}.sh", std::process::id()));
+        std::fs::write(&script, concat!(
+            "while read line; do\n",
+            "  echo '{\"turn\":999,\"column\":5,\"orientation\":\"up\"}'\n",
+            "  turn=$(echo \"$line\" | sed 's/.*\"turn\":\\([0-9]*\\).*/\\1/')\n",
+            "  echo \"{\\\"turn\\\":$turn,\\\"column\\\":0,",
+            "\\\"orientation\\\":\\\"up\\\"}\"\n",
+            "done\n")).unwrap();
+        let command = format!("sh {}", script.display());
+        let mut bot = ExternalBot::spawn(&command, Duration::from_secs(5))
+            .unwrap();
+        let faults = bot.faults();
+        let mut game = Game::with_seed(1);
+        while game.pair_number() < 3 {
+            game.tick_with(&mut bot);
+        }
+        std::fs::remove_file(&script).unwrap();
+        assert_eq!(game.last_placement(), Some((0, Direction::Up)));
+        assert_eq!(*faults.lock().unwrap(), Faults::default());
+    }
+}
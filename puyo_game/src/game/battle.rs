@@ -0,0 +1,543 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use super::Game;
+use super::controller::Controller;
+use super::placement::Input;
+use super::versus::Outcome;
+
+/// Who a player's garbage goes to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Targeting {
+    /// Whoever last sent garbage this way, or failing that whoever is aiming
+    /// here.
+    Attacker,
+    /// Someone new at random after every attack.
+    Random,
+    /// Whoever has the highest score.
+    HighestScore,
+    /// Always this player, for as long as they are in.
+    Manual(usize),
+}
+
+/// Any number of games sending garbage to each other, each played by a
+/// `Controller` and aiming at one opponent at a time.
+///
+/// Players can be put in teams. Teammates never aim at each other and share
+/// their garbage: what one of them sends first cancels what is waiting for
+/// the others, and garbage sent to a team falls on whichever member has the
+/// least waiting. A team is out once all of its players are.
+///
+/// Players who top out drop out: they stop ticking, their garbage goes
+/// nowhere and nobody aims at them. Like `Versus`, every input is recorded,
+/// and so is every change of targeting; the same seed and recordings, with
+/// the targeting changes handed to `replay_targeting`, play the same match.
+pub struct Battle {
+    seed: u64,
+    games: Vec<Game>,
+    controllers: Vec<Box<dyn Controller>>,
+    teams: Vec<usize>,
+    recordings: Vec<Vec<(u32, Input)>>,
+    // ticks since the start
+    frame: u32,
+    targeting_changes: Vec<(u32, usize, Targeting)>,
+    // changes to make again, and how many of them have been made
+    replayed_targeting: Vec<(u32, usize, Targeting)>,
+    replay_position: usize,
+    targeting: Vec<Targeting>,
+    targets: Vec<Option<usize>>,
+    // who last sent garbage to each player
+    attackers: Vec<Option<usize>>,
+    eliminated: Vec<usize>,
+    rng: StdRng,
+}
+
+impl Battle {
+    pub fn new(controllers: Vec<Box<dyn Controller>>) -> Battle {
+        Battle::with_seed(rand::random(), controllers)
+    }
+
+    /// Like `new`, with `teams` giving each player's team number.
+    pub fn new_teams(controllers: Vec<Box<dyn Controller>>,
+                     teams: Vec<usize>) -> Battle {
+        Battle::with_teams(rand::random(), controllers, teams)
+    }
+
+    /// Like `new`, but every game deals the same pairs in the same order,
+    /// fixed by `seed`. Everyone starts out targeting their attacker.
+    pub fn with_seed(seed: u64, controllers: Vec<Box<dyn Controller>>)
+            -> Battle {
+        let teams = (0..controllers.len()).collect();
+        Battle::with_teams(seed, controllers, teams)
+    }
+
+    /// Like `with_seed`, with `teams` giving each player's team number.
+    pub fn with_teams(seed: u64, controllers: Vec<Box<dyn Controller>>,
+                      teams: Vec<usize>) -> Battle {
+        assert_eq!(teams.len(), controllers.len());
+        let mut battle = Battle {
+            seed,
+            games: Vec::new(),
+            controllers,
+            teams,
+            recordings: Vec::new(),
+            frame: 0,
+            targeting_changes: Vec::new(),
+            replayed_targeting: Vec::new(),
+            replay_position: 0,
+            targeting: Vec::new(),
+            targets: Vec::new(),
+            attackers: Vec::new(),
+            eliminated: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        };
+        battle.start(seed);
+        battle
+    }
+
+    fn start(&mut self, seed: u64) {
+        let players = self.players();
+        self.seed = seed;
+        self.games = vec![Game::with_seed(seed); players];
+        self.recordings = vec![Vec::new(); players];
+        self.frame = 0;
+        self.targeting = vec![Targeting::Attacker; players];
+        self.targeting_changes = Vec::new();
+        self.replay_position = 0;
+        self.targets = vec![None; players];
+        self.attackers = vec![None; players];
+        self.eliminated = Vec::new();
+        self.rng = StdRng::seed_from_u64(seed);
+        self.retarget();
+    }
+
+    pub fn players(&self) -> usize {
+        self.controllers.len()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Every (frame, input) `player` has made so far.
+    pub fn recording(&self, player: usize) -> &[(u32, Input)] {
+        &self.recordings[player]
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        &self.games[player]
+    }
+
+    pub fn controller(&mut self, player: usize) -> &mut dyn Controller {
+        self.controllers[player].as_mut()
+    }
+
+    pub fn set_controller(&mut self, player: usize,
+                          controller: Box<dyn Controller>) {
+        self.controllers[player] = controller;
+    }
+
+    pub fn targeting(&self, player: usize) -> Targeting {
+        self.targeting[player]
+    }
+
+    /// Every (frame, player, targeting) change made so far, where the frame
+    /// is how many times the battle had ticked.
+    pub fn targeting_changes(&self) -> &[(u32, usize, Targeting)] {
+        &self.targeting_changes
+    }
+
+    /// Make `changes`, as taken from `targeting_changes`, again on the
+    /// frames they were first made on.
+    pub fn replay_targeting(&mut self, changes: Vec<(u32, usize, Targeting)>) {
+        self.replayed_targeting = changes;
+        self.replay_position = 0;
+    }
+
+    pub fn set_targeting(&mut self, player: usize, targeting: Targeting) {
+        self.targeting_changes.push((self.frame, player, targeting));
+        self.targeting[player] = targeting;
+        if let Targeting::Random = targeting {
+            self.targets[player] = None;
+        }
+        self.retarget();
+    }
+
+    /// Who `player`'s garbage is going to, if anyone is left.
+    pub fn target(&self, player: usize) -> Option<usize> {
+        self.targets[player]
+    }
+
+    pub fn is_eliminated(&self, player: usize) -> bool {
+        self.games[player].is_over()
+    }
+
+    pub fn team(&self, player: usize) -> usize {
+        self.teams[player]
+    }
+
+    /// The players in `team`.
+    pub fn members(&self, team: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.players()).filter(move |player| self.teams[*player] == team)
+    }
+
+    /// Whether every player in `team` has topped out.
+    pub fn is_team_out(&self, team: usize) -> bool {
+        self.members(team).all(|player| self.is_eliminated(player))
+    }
+
+    /// Players who have topped out, first out first. Players who top out
+    /// on the same tick are in index order.
+    pub fn eliminated(&self) -> &[usize] {
+        &self.eliminated
+    }
+
+    /// Start over with fresh games, keeping the same controllers. Everyone
+    /// goes back to targeting their attacker, and targeting changes handed
+    /// to `replay_targeting` are forgotten.
+    pub fn restart(&mut self) {
+        self.replayed_targeting = Vec::new();
+        self.start(rand::random());
+        for controller in self.controllers.iter_mut() {
+            controller.reset();
+        }
+    }
+
+    /// The winner is a team number, which is the player's index when
+    /// nobody is on a team together.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let mut teams: Vec<usize> = (0..self.players())
+            .filter(|player| !self.is_eliminated(*player))
+            .map(|player| self.teams[player])
+            .collect();
+        teams.dedup();
+        let mut left = teams.into_iter();
+        match (left.next(), left.next()) {
+            (None, _) => Some(Outcome::Draw),
+            (Some(winner), None) => Some(Outcome::Winner(winner)),
+            (Some(_), Some(_)) => None,
+        }
+    }
+
+    /// Tick every game still in and send each one's garbage to its target,
+    /// until one team is left.
+    pub fn tick(&mut self) -> Option<Outcome> {
+        if let Some(outcome) = self.outcome() {
+            return Some(outcome);
+        }
+        while let Some((frame, player, targeting)) =
+                self.replayed_targeting.get(self.replay_position).copied() {
+            if frame > self.frame {
+                break;
+            }
+            self.replay_position += 1;
+            self.set_targeting(player, targeting);
+        }
+        self.frame += 1;
+        for player in 0..self.players() {
+            if self.is_eliminated(player) {
+                continue;
+            }
+            let game = &mut self.games[player];
+            let inputs = self.controllers[player].poll(game);
+            let frame = game.frame();
+            self.recordings[player].extend(
+                inputs.iter().map(|input| (frame, *input)));
+            for input in inputs {
+                game.input(input);
+            }
+            game.tick();
+        }
+        for player in 0..self.players() {
+            if self.is_eliminated(player) && !self.eliminated.contains(&player) {
+                self.eliminated.push(player);
+            }
+        }
+        self.retarget();
+        for player in 0..self.players() {
+            let garbage = self.games[player].get_garbage();
+            if garbage == 0 || self.is_eliminated(player) {
+                continue;
+            }
+            self.send(player, garbage);
+        }
+        self.retarget();
+        self.outcome()
+    }
+
+    // offset what is waiting for `player`'s teammates, then drop the rest
+    // on the target's team
+    fn send(&mut self, player: usize, garbage: u32) {
+        let team = self.teams[player];
+        let teammates: Vec<usize> = self.members(team)
+            .filter(|other| *other != player && !self.is_eliminated(*other))
+            .collect();
+        let mut garbage = garbage;
+        for teammate in teammates {
+            garbage -= self.games[teammate].cancel_garbage(garbage);
+        }
+        let target = match self.targets[player] {
+            Some(target) if garbage > 0 => target,
+            _ => return,
+        };
+        let target_team = self.teams[target];
+        let landing = self.members(target_team)
+            .filter(|other| !self.is_eliminated(*other))
+            .min_by_key(|other| {
+                (self.games[*other].pending_garbage(), *other != target)
+            })
+            .unwrap_or(target);
+        self.games[landing].add_garbage(garbage);
+        let defenders: Vec<usize> = self.members(target_team).collect();
+        for defender in defenders {
+            self.attackers[defender] = Some(player);
+        }
+        if let Targeting::Random = self.targeting[player] {
+            self.targets[player] = None;
+        }
+    }
+
+    // point everyone still in at someone still in, by their targeting
+    fn retarget(&mut self) {
+        for player in 0..self.players() {
+            self.targets[player] = if self.is_eliminated(player) {
+                None
+            } else {
+                self.pick_target(player)
+            };
+        }
+    }
+
+    fn pick_target(&mut self, player: usize) -> Option<usize> {
+        let players = self.players();
+        let opponents: Vec<usize> = (1..players)
+            .map(|offset| (player + offset) % players)
+            .filter(|other| !self.is_eliminated(*other))
+            .filter(|other| self.teams[*other] != self.teams[player])
+            .collect();
+        let current = self.targets[player]
+            .filter(|target| opponents.contains(target));
+        let wanted = match self.targeting[player] {
+            Targeting::Attacker => self.attackers[player]
+                .filter(|attacker| opponents.contains(attacker))
+                .or_else(|| {
+                    opponents.iter().copied()
+                        .find(|other| self.targets[*other] == Some(player))
+                })
+                .or(current),
+            Targeting::Random => {
+                current.or_else(|| opponents.choose(&mut self.rng).copied())
+            }
+            Targeting::HighestScore => opponents.iter().copied()
+                .max_by_key(|other| {
+                    (self.games[*other].score(), players - other)
+                }),
+            Targeting::Manual(target) => {
+                Some(target).filter(|target| opponents.contains(target))
+            }
+        };
+        wanted.or_else(|| opponents.first().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{Cpu, Difficulty};
+    use crate::game::controller::Replay;
+
+    // players who never press anything, each stacking pairs up the middle
+    fn idle_battle(players: usize) -> Battle {
+        let controllers = (0..players)
+            .map(|_| Box::new(Replay::new(Vec::new())) as Box<dyn Controller>)
+            .collect();
+        Battle::with_seed(7, controllers)
+    }
+
+    // tick until `player` tops out, which garbage makes happen sooner
+    fn bury(battle: &mut Battle, player: usize, garbage: u32) {
+        battle.games[player].add_garbage(garbage);
+        while !battle.is_eliminated(player) {
+            battle.tick();
+        }
+    }
+
+    #[test]
+    fn attacker_targeting_falls_back_to_whoever_aims_here() {
+        let mut battle = idle_battle(3);
+        // nobody has attacked yet: 0 takes the next player along, 1 aims
+        // back at 0 and 2 takes the first player along
+        assert_eq!(battle.target(0), Some(1));
+        assert_eq!(battle.target(1), Some(0));
+        assert_eq!(battle.target(2), Some(0));
+        battle.send(2, 5);
+        battle.retarget();
+        assert_eq!(battle.game(0).pending_garbage(), 5);
+        assert_eq!(battle.target(0), Some(2));
+    }
+
+    #[test]
+    fn random_targeting_rerolls_after_every_attack() {
+        let mut battle = idle_battle(4);
+        battle.set_targeting(0, Targeting::Random);
+        let mut hit = [0; 4];
+        for _ in 0..100 {
+            let target = battle.target(0).unwrap();
+            hit[target] += 1;
+            battle.send(0, 1);
+            battle.retarget();
+        }
+        assert_eq!(hit[0], 0);
+        assert!(hit[1..].iter().all(|count| *count > 0));
+    }
+
+    #[test]
+    fn highest_score_ties_go_to_the_lowest_player() {
+        let mut battle = idle_battle(4);
+        for player in 0..4 {
+            battle.set_targeting(player, Targeting::HighestScore);
+        }
+        assert_eq!(battle.target(0), Some(1));
+        assert_eq!(battle.target(1), Some(0));
+        assert_eq!(battle.target(3), Some(0));
+    }
+
+    #[test]
+    fn manual_targeting_moves_off_eliminated_players() {
+        let mut battle = idle_battle(3);
+        battle.set_targeting(0, Targeting::Manual(1));
+        assert_eq!(battle.target(0), Some(1));
+        bury(&mut battle, 1, 60);
+        assert_eq!(battle.eliminated(), &[1]);
+        assert_eq!(battle.targeting(0), Targeting::Manual(1));
+        assert_eq!(battle.target(0), Some(2));
+        assert_eq!(battle.target(1), None);
+    }
+
+    #[test]
+    fn last_player_in_wins() {
+        let mut battle = idle_battle(3);
+        bury(&mut battle, 2, 60);
+        assert_eq!(battle.outcome(), None);
+        bury(&mut battle, 1, 60);
+        assert_eq!(battle.eliminated(), &[2, 1]);
+        assert_eq!(battle.outcome(), Some(Outcome::Winner(0)));
+        assert_eq!(battle.tick(), Some(Outcome::Winner(0)));
+    }
+
+    #[test]
+    fn same_tick_eliminations_are_in_player_order_and_draw() {
+        let mut battle = idle_battle(2);
+        let mut outcome = None;
+        while outcome.is_none() {
+            outcome = battle.tick();
+        }
+        assert_eq!(outcome, Some(Outcome::Draw));
+        assert_eq!(battle.eliminated(), &[0, 1]);
+    }
+
+    #[test]
+    fn teammates_offset_each_others_garbage() {
+        let controllers = (0..4)
+            .map(|_| Box::new(Replay::new(Vec::new())) as Box<dyn Controller>)
+            .collect();
+        let mut battle = Battle::with_teams(7, controllers, vec![0, 0, 1, 1]);
+        assert!(battle.target(0).is_some_and(|target| battle.team(target) == 1));
+        battle.games[1].add_garbage(10);
+        battle.send(0, 6);
+        assert_eq!(battle.game(1).pending_garbage(), 4);
+        assert_eq!(battle.game(2).pending_garbage(), 0);
+        assert_eq!(battle.game(3).pending_garbage(), 0);
+        battle.send(0, 10);
+        assert_eq!(battle.game(1).pending_garbage(), 0);
+        let target = battle.target(0).unwrap();
+        assert_eq!(battle.game(target).pending_garbage(), 6);
+        // the rest goes to whoever on the team has the least waiting
+        battle.send(0, 2);
+        let other = if target == 2 { 3 } else { 2 };
+        assert_eq!(battle.game(other).pending_garbage(), 2);
+    }
+
+    // a battle replaying `played` from its seed, recordings and targeting
+    // changes
+    fn replay_of(played: &Battle) -> Battle {
+        let controllers = (0..played.players())
+            .map(|player| {
+                let inputs = played.recording(player).to_vec();
+                Box::new(Replay::new(inputs)) as Box<dyn Controller>
+            })
+            .collect();
+        let mut replayed = Battle::with_seed(played.seed(), controllers);
+        replayed.replay_targeting(played.targeting_changes().to_vec());
+        replayed
+    }
+
+    #[test]
+    fn recordings_and_targeting_changes_replay_the_match() {
+        let controllers = (0..3)
+            .map(|_| Box::new(Cpu::new(Difficulty::Easy)) as Box<dyn Controller>)
+            .collect();
+        let mut played = Battle::with_seed(11, controllers);
+        let mut targets = Vec::new();
+        for frame in 0..2_000 {
+            match frame {
+                300 => played.set_targeting(0, Targeting::Random),
+                900 => played.set_targeting(1, Targeting::HighestScore),
+                1_500 => played.set_targeting(2, Targeting::Manual(1)),
+                _ => {}
+            }
+            played.tick();
+            targets.push((0..3).map(|p| played.target(p)).collect::<Vec<_>>());
+        }
+
+        let mut replayed = replay_of(&played);
+        for expected in targets {
+            replayed.tick();
+            let got: Vec<_> = (0..3).map(|p| replayed.target(p)).collect();
+            assert_eq!(got, expected);
+        }
+        for player in 0..3 {
+            assert_eq!(replayed.game(player).board(), played.game(player).board());
+            assert_eq!(replayed.targeting(player), played.targeting(player));
+        }
+        assert_eq!(replayed.eliminated(), played.eliminated());
+        assert_eq!(replayed.targeting_changes(), played.targeting_changes());
+    }
+
+    #[test]
+    fn restarted_battles_replay_from_their_seed() {
+        let controllers = (0..3)
+            .map(|_| Box::new(Cpu::new(Difficulty::Easy)) as Box<dyn Controller>)
+            .collect();
+        let mut played = Battle::with_seed(11, controllers);
+        played.replay_targeting(vec![(700, 2, Targeting::Random)]);
+        played.set_targeting(0, Targeting::Manual(2));
+        for _ in 0..600 {
+            played.tick();
+        }
+        played.restart();
+        assert_eq!(played.targeting(0), Targeting::Attacker);
+        played.set_targeting(1, Targeting::HighestScore);
+        let mut targets = Vec::new();
+        for frame in 0..1_500 {
+            if frame == 400 {
+                played.set_targeting(0, Targeting::Random);
+            }
+            played.tick();
+            targets.push((0..3).map(|p| played.target(p)).collect::<Vec<_>>());
+        }
+        // the change queued before the restart is never made
+        assert_eq!(played.targeting(2), Targeting::Attacker);
+
+        let mut replayed = replay_of(&played);
+        for expected in targets {
+            replayed.tick();
+            let got: Vec<_> = (0..3).map(|p| replayed.target(p)).collect();
+            assert_eq!(got, expected);
+        }
+        for player in 0..3 {
+            assert_eq!(replayed.game(player).board(), played.game(player).board());
+            assert_eq!(replayed.targeting(player), played.targeting(player));
+        }
+    }
+}
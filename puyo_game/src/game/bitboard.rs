@@ -0,0 +1,283 @@
+use super::color::Color;
+use super::color::Color::*;
+use super::util::*;
+use super::board::{Board, Illegal, drop_order, landing_rows};
+use super::chain::{ChainTracker, ChainResult};
+use super::{BOARD_WIDTH, BOARD_HEIGHT, DROP_POS};
+
+// Cell (x, y) is bit `y * STRIDE + x`, so the lowest set bit is always the
+// first cell `Board` would reach scanning rows from the bottom up.
+const STRIDE: usize = 8;
+const ROW: u128 = (1 << BOARD_WIDTH) - 1;
+const COLUMN: u128 = rows_mask(BOARD_HEIGHT, 1);
+const FIELD: u128 = rows_mask(BOARD_HEIGHT, ROW);
+const VISIBLE: u128 = rows_mask(BOARD_HEIGHT - 1, ROW);
+
+const _: () = assert!(BOARD_WIDTH < STRIDE && BOARD_HEIGHT * STRIDE <= 128);
+
+const LAYERS: [Color; 6] = [Garbage, Red, Green, Blue, Yellow, Violet];
+const GARBAGE_LAYER: usize = 0;
+
+const fn rows_mask(rows: usize, row: u128) -> u128 {
+    let mut mask = 0;
+    let mut y = 0;
+    while y < rows {
+        mask |= row << (y * STRIDE);
+        y += 1;
+    }
+    mask
+}
+
+fn bit(x: usize, y: usize) -> u128 {
+    1 << (y * STRIDE + x)
+}
+
+fn layer_index(color: Color) -> Option<usize> {
+    LAYERS.iter().position(|c| *c == color)
+}
+
+fn neighbors(bits: u128) -> u128 {
+    bits | bits << 1 | bits >> 1 | bits << STRIDE | bits >> STRIDE
+}
+
+fn flood(seed: u128, mask: u128) -> u128 {
+    let mut group = seed;
+    loop {
+        let grown = neighbors(group) & mask;
+        if grown == group {
+            return group;
+        }
+        group = grown;
+    }
+}
+
+/// A `Board` packed into one bitmask per color, for search code that needs
+/// to simulate a lot of placements.
+///
+/// Gravity, chains and scoring give exactly the same results as `Board`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BitBoard {
+    layers: [u128; 6],
+}
+
+impl BitBoard {
+    pub fn new() -> BitBoard {
+        BitBoard::default()
+    }
+
+    pub fn get(&self, coord: &Coord) -> Option<Color> {
+        if (coord.x < 0 || coord.y < 0
+            || coord.x as usize >= BOARD_WIDTH
+            || coord.y as usize >= BOARD_HEIGHT) {
+            return None;
+        }
+        let mask = bit(coord.x as usize, coord.y as usize);
+        let color = LAYERS.iter().zip(self.layers.iter())
+            .find(|(_, layer)| *layer & mask != 0)
+            .map_or(Empty, |(color, _)| *color);
+        Some(color)
+    }
+
+    /// Put `color` at `coord`, returning what was there before.
+    pub fn set(&mut self, coord: &Coord, color: Color) -> Option<Color> {
+        let orig = self.get(coord)?;
+        let mask = bit(coord.x as usize, coord.y as usize);
+        for layer in self.layers.iter_mut() {
+            *layer &= !mask;
+        }
+        if let Some(index) = layer_index(color) {
+            self.layers[index] |= mask;
+        }
+        Some(orig)
+    }
+
+    pub fn occupied(&self) -> u128 {
+        self.layers.iter().fold(0, |acc, layer| acc | layer)
+    }
+
+    /// Same as `Board::spawn_blocked`.
+    pub fn spawn_blocked(&self) -> bool {
+        self.occupied() & bit(DROP_POS.x as usize, DROP_POS.y as usize) != 0
+    }
+
+    /// Every connected group of one color in the visible rows, as
+    /// (color, cells) with the cells as a mask in this board's layout.
+    pub fn groups(&self) -> Vec<(Color, u128)> {
+        let mut groups = Vec::new();
+        for (index, color) in LAYERS.iter().enumerate().skip(1) {
+            let color_mask = self.layers[index] & VISIBLE;
+            let mut unchecked = color_mask;
+            while unchecked != 0 {
+                let group = flood(unchecked & unchecked.wrapping_neg(),
+                                  color_mask);
+                unchecked &= !group;
+                groups.push((*color, group));
+            }
+        }
+        groups
+    }
+
+    pub fn column_height(&self, x: usize) -> usize {
+        let column = self.occupied() & (COLUMN << x);
+        if column == 0 {
+            0
+        } else {
+            (127 - column.leading_zeros() as usize) / STRIDE + 1
+        }
+    }
+
+    /// Same as `Board::drop_pair`.
+    pub fn drop_pair(&mut self, pair: (Color, Color), column: usize,
+                     orientation: Direction) -> Result<(), Illegal> {
+        let order = drop_order(pair, column, orientation)?;
+        let landing = landing_rows(&order, |x| self.column_height(x))?;
+        for ((x, color), y) in order.iter().zip(landing.iter()) {
+            if let Some(index) = layer_index(*color) {
+                self.layers[index] |= bit(*x, *y);
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as `Board::place`.
+    pub fn place(&mut self, pair: (Color, Color), column: usize,
+                 orientation: Direction) -> Result<ChainResult, Illegal> {
+        self.drop_pair(pair, column, orientation)?;
+        Ok(self.resolve_chains())
+    }
+
+    /// Apply gravity until nothing is left floating.
+    pub fn settle(&mut self) -> bool {
+        let mut did_something = false;
+        loop {
+            let occupied = self.occupied();
+            // every cell with a hole somewhere beneath it drops one row
+            let mut above_hole = (!occupied & FIELD) << STRIDE;
+            above_hole |= above_hole << STRIDE;
+            above_hole |= above_hole << (2 * STRIDE);
+            above_hole |= above_hole << (4 * STRIDE);
+            above_hole |= above_hole << (8 * STRIDE);
+            let falling = occupied & above_hole & FIELD;
+            if falling == 0 {
+                return did_something;
+            }
+            for layer in self.layers.iter_mut() {
+                *layer = (*layer & !falling) | ((*layer & falling) >> STRIDE);
+            }
+            did_something = true;
+        }
+    }
+
+    /// Same as `Board::pop_groups`.
+    pub fn pop_groups(&mut self, chain: &mut ChainTracker) -> bool {
+        let mut unchecked = (self.occupied()
+                             & !self.layers[GARBAGE_LAYER]
+                             & VISIBLE);
+        let mut any_cleared = false;
+
+        while unchecked != 0 {
+            let seed = unchecked & unchecked.wrapping_neg();
+            let index = (1..LAYERS.len())
+                .find(|i| self.layers[*i] & seed != 0)
+                .unwrap();
+            let group = flood(seed, self.layers[index] & VISIBLE);
+            unchecked &= !group;
+            if group.count_ones() < 4 {
+                continue;
+            }
+            any_cleared = true;
+            let garbage = (neighbors(group)
+                           & self.layers[GARBAGE_LAYER]
+                           & VISIBLE);
+            self.layers[index] &= !group;
+            self.layers[GARBAGE_LAYER] &= !garbage;
+            chain.record_group(
+                LAYERS[index], group.count_ones(), garbage.count_ones());
+        }
+
+        if any_cleared {
+            chain.end_cycle();
+        }
+
+        any_cleared
+    }
+
+    /// Same as `Board::resolve_chains`.
+    pub fn resolve_chains(&mut self) -> ChainResult {
+        let mut chain = ChainTracker::new();
+        loop {
+            self.settle();
+            if !self.pop_groups(&mut chain) {
+                break;
+            }
+        }
+        chain.finish()
+    }
+}
+
+impl From<&Board> for BitBoard {
+    fn from(board: &Board) -> Self {
+        let mut bits = BitBoard::new();
+        for y in 0..BOARD_HEIGHT {
+            for x in 0..BOARD_WIDTH {
+                let coord = Coord { x: x as i32, y: y as i32 };
+                if let Some(color) = board.get(&coord) {
+                    bits.set(&coord, color);
+                }
+            }
+        }
+        bits
+    }
+}
+
+impl From<&BitBoard> for Board {
+    fn from(bits: &BitBoard) -> Self {
+        let mut board = Board::new();
+        for y in 0..BOARD_HEIGHT {
+            for x in 0..BOARD_WIDTH {
+                let coord = Coord { x: x as i32, y: y as i32 };
+                if let Some(color) = bits.get(&coord) {
+                    board.set(&coord, color);
+                }
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+
+    // columns of random height with random holes, so boards have floating
+    // puyo, garbage and groups both big enough and too small to pop
+    fn random_board(rng: &mut StdRng) -> Board {
+        let colors = [Red, Green, Blue, Yellow, Violet, Garbage];
+        let mut board = Board::new();
+        for x in 0..BOARD_WIDTH {
+            let height = rng.gen_range(0, BOARD_HEIGHT + 1);
+            for y in 0..height {
+                if rng.gen_bool(0.15) {
+                    continue;
+                }
+                let coord = Coord { x: x as i32, y: y as i32 };
+                board.set(&coord, *colors.choose(rng).unwrap());
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn resolve_chains_matches_board() {
+        let mut rng = StdRng::seed_from_u64(27);
+        for _ in 0..20_000 {
+            let mut board = random_board(&mut rng);
+            let mut bits = BitBoard::from(&board);
+            let expected = board.resolve_chains();
+            assert_eq!(bits.resolve_chains(), expected);
+            assert_eq!(Board::from(&bits), board);
+        }
+    }
+}
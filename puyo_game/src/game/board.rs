@@ -0,0 +1,410 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::color::Color;
+use super::color::Color::*;
+use super::util::*;
+use super::chain::{ChainTracker, ChainResult};
+use super::placement::{self, Placement};
+use super::puyo::Puyo;
+use super::{BOARD_WIDTH, BOARD_HEIGHT, DROP_POS};
+
+const GARBAGE_COLUMNS: [usize; 6] = [0, 3, 2, 5, 1, 4];
+// the top row is hidden and never takes part in chains
+const VISIBLE_HEIGHT: usize = BOARD_HEIGHT - 1;
+
+fn is_visible<const W: usize>(coord: &Coord) -> bool {
+    (coord.x >= 0 && coord.y >= 0
+     && (coord.x as usize) < W
+     && (coord.y as usize) < VISIBLE_HEIGHT)
+}
+
+/// Why a pair could not be placed on a `Board`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Illegal {
+    /// The pivot or wheel would be outside the walls.
+    OutOfBounds,
+    /// The orientation was `Direction::None`.
+    NoOrientation,
+    /// The pair would land above the top row.
+    ColumnFull,
+}
+
+/// Why a text layout could not be read as a `Board`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParseBoardError {
+    /// A row, counted from the top of the text, was the wrong width.
+    BadRow(usize),
+    BadColor(char),
+    TooManyRows,
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBoardError::BadRow(row) => write!(
+                f, "row {} is not {} cells wide", row + 1, BOARD_WIDTH),
+            ParseBoardError::BadColor(letter) => write!(
+                f, "{:?} is not a color", letter),
+            ParseBoardError::TooManyRows => write!(
+                f, "more than {} rows", BOARD_HEIGHT),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+/// Work out which columns a pair drops into, lower puyo first.
+pub(crate) fn drop_order(pair: (Color, Color), column: usize,
+                         orientation: Direction)
+        -> Result<[(usize, Color); 2], Illegal> {
+    let (pivot, wheel) = pair;
+    let wheel_column = match orientation {
+        Direction::None => return Err(Illegal::NoOrientation),
+        Direction::Left => column.checked_sub(1),
+        Direction::Right => Some(column + 1),
+        Direction::Up | Direction::Down => Some(column),
+    };
+    let wheel_column = match wheel_column {
+        Some(x) if x < BOARD_WIDTH && column < BOARD_WIDTH => x,
+        _ => return Err(Illegal::OutOfBounds),
+    };
+    Ok(match orientation {
+        Direction::Down => [(wheel_column, wheel), (column, pivot)],
+        _ => [(column, pivot), (wheel_column, wheel)],
+    })
+}
+
+/// Find the row each puyo from `drop_order` comes to rest on.
+pub(crate) fn landing_rows<F>(order: &[(usize, Color); 2], column_height: F)
+        -> Result<[usize; 2], Illegal>
+        where F: Fn(usize) -> usize {
+    let mut landing = [0; 2];
+    for (i, (x, _)) in order.iter().enumerate() {
+        let mut y = column_height(*x);
+        if i == 1 && *x == order[0].0 {
+            y += 1;
+        }
+        if y >= BOARD_HEIGHT {
+            return Err(Illegal::ColumnFull);
+        }
+        landing[i] = y;
+    }
+    Ok(landing)
+}
+
+/// The playing field on its own, without timers, input or randomness.
+///
+/// `y` is counted from the bottom row, and the top row is hidden: puyo
+/// there never take part in chains.
+///
+/// Boards are `BOARD_WIDTH` wide unless `W` says otherwise. Only gravity,
+/// chains and reading cells work at other widths; dropping pairs,
+/// placements, garbage and the text layout are for the usual width.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Board<const W: usize = BOARD_WIDTH> {
+    cells: [[Color; W]; BOARD_HEIGHT],
+}
+
+impl<const W: usize> Default for Board<W> {
+    fn default() -> Self {
+        Board { cells: [[Empty; W]; BOARD_HEIGHT] }
+    }
+}
+
+impl<const W: usize> Board<W> {
+    pub fn get(&self, coord: &Coord) -> Option<Color> {
+        if coord.x < 0 || coord.y < 0 {
+            return None;
+        }
+        self.cells.get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
+    }
+
+    pub fn is_empty(&self, coord: &Coord) -> bool {
+        self.get(coord) == Some(Empty)
+    }
+
+    /// Put `color` at `coord`, returning what was there before, or `None`
+    /// if `coord` is off the board.
+    pub fn set(&mut self, coord: &Coord, color: Color) -> Option<Color> {
+        if coord.x < 0 || coord.y < 0 {
+            return None;
+        }
+        self.cells.get_mut(coord.y as usize)
+            .and_then(|row| row.get_mut(coord.x as usize))
+            .map(|item| std::mem::replace(item, color))
+    }
+
+    /// Rows from the bottom of the board up.
+    pub fn rows(&self) -> std::slice::Iter<'_, [Color; W]> {
+        self.cells.iter()
+    }
+
+    /// One column from the bottom of the board up.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = Color> + '_ {
+        self.cells.iter().filter_map(move |row| row.get(x).copied())
+    }
+
+    /// Move every floating puyo down by one row.
+    pub fn step_gravity(&mut self) -> bool {
+        self.step_gravity_around(|_| false)
+    }
+
+    /// Same as `step_gravity`, but nothing falls into a cell `held` says
+    /// is taken by something that isn't on the board, like a pair in the
+    /// air.
+    pub(crate) fn step_gravity_around<F>(&mut self, held: F) -> bool
+            where F: Fn(&Coord) -> bool {
+        let mut did_something = false;
+
+        for above_index in 1..self.cells.len() {
+            let (lower_rows, upper_rows) = self.cells.split_at_mut(above_index);
+            let row = lower_rows.last_mut().unwrap();
+            let above = upper_rows.first_mut().unwrap();
+            for (x, (item, item_above)) in row.iter_mut()
+                    .zip(above.iter_mut()).enumerate() {
+                let below = Coord { x: x as i32, y: above_index as i32 - 1 };
+                if *item == Empty && *item_above != Empty && !held(&below) {
+                    *item = *item_above;
+                    *item_above = Empty;
+                    did_something = true;
+                }
+            }
+        }
+        did_something
+    }
+
+    /// Apply gravity until nothing is left floating.
+    pub fn settle(&mut self) -> bool {
+        let mut did_something = false;
+        while self.step_gravity() {
+            did_something = true;
+        }
+        did_something
+    }
+
+    /// Pop every group of four or more, along with any garbage touching
+    /// them, recording each group in `chain`.
+    pub fn pop_groups(&mut self, chain: &mut ChainTracker) -> bool {
+        let mut visited = [[false; W]; VISIBLE_HEIGHT];
+        // stacks of cells, the nth of which is at [n / W][n % W]
+        let mut pending = [[Coord { x: 0, y: 0 }; W]; VISIBLE_HEIGHT];
+        let mut group = [[Coord { x: 0, y: 0 }; W]; VISIBLE_HEIGHT];
+        let mut any_cleared = false;
+
+        for y in 0..VISIBLE_HEIGHT {
+            for x in 0..W {
+                if visited[y][x] {
+                    continue;
+                }
+                let blob_color = match self.cells[y][x] {
+                    color if color.is_normal() => color,
+                    _ => continue,
+                };
+                visited[y][x] = true;
+                pending[0][0] = Coord { x: x as i32, y: y as i32 };
+                let mut num_pending = 1;
+                let mut group_len = 0;
+                while num_pending > 0 {
+                    num_pending -= 1;
+                    let item = pending[num_pending / W][num_pending % W];
+                    group[group_len / W][group_len % W] = item;
+                    group_len += 1;
+                    for dir in Direction::each_real() {
+                        let possible = item.apply_motion(*dir);
+                        if !is_visible::<W>(&possible) {
+                            continue;
+                        }
+                        let (px, py) = (possible.x as usize, possible.y as usize);
+                        if visited[py][px] || self.cells[py][px] != blob_color {
+                            continue;
+                        }
+                        visited[py][px] = true;
+                        pending[num_pending / W][num_pending % W] = possible;
+                        num_pending += 1;
+                    }
+                }
+                if group_len < 4 {
+                    continue;
+                }
+                any_cleared = true;
+                let mut garbage = 0;
+                for puyo in group.iter().flat_map(|row| row.iter())
+                        .take(group_len) {
+                    self.cells[puyo.y as usize][puyo.x as usize] = Empty;
+                }
+                // clearing garbage as it is found means it is never
+                // counted twice
+                for puyo in group.iter().flat_map(|row| row.iter())
+                        .take(group_len) {
+                    for dir in Direction::each_real() {
+                        let possible = puyo.apply_motion(*dir);
+                        if (is_visible::<W>(&possible)
+                            && self.get(&possible) == Some(Garbage)) {
+                            self.set(&possible, Empty);
+                            garbage += 1;
+                        }
+                    }
+                }
+                chain.record_group(blob_color, group_len as u32, garbage);
+            }
+        }
+
+        if any_cleared {
+            chain.end_cycle();
+        }
+
+        any_cleared
+    }
+
+    /// Settle and pop groups until the board is stable.
+    pub fn resolve_chains(&mut self) -> ChainResult {
+        let mut chain = ChainTracker::new();
+        loop {
+            self.settle();
+            if !self.pop_groups(&mut chain) {
+                break;
+            }
+        }
+        chain.finish()
+    }
+}
+
+impl Board {
+    pub fn new() -> Board {
+        Board::filled(Empty)
+    }
+
+    pub fn filled(color: Color) -> Board {
+        Board {
+            cells: [[color; BOARD_WIDTH]; BOARD_HEIGHT],
+        }
+    }
+
+    fn column_height(&self, x: usize) -> usize {
+        (0..BOARD_HEIGHT).rev()
+            .find(|&y| self.cells[y][x] != Empty)
+            .map_or(0, |y| y + 1)
+    }
+
+    /// Where each puyo of a pair dropped into `column` comes to rest, and
+    /// with which color, without changing the board.
+    pub fn landing(&self, pair: (Color, Color), column: usize,
+                   orientation: Direction) -> Result<[(Coord, Color); 2], Illegal> {
+        let order = drop_order(pair, column, orientation)?;
+        let landing = landing_rows(&order, |x| self.column_height(x))?;
+        let cell = |i: usize| {
+            let (x, color) = order[i];
+            (Coord { x: x as i32, y: landing[i] as i32 }, color)
+        };
+        Ok([cell(0), cell(1)])
+    }
+
+    /// Drop a (pivot, wheel) pair into `column` without resolving anything.
+    ///
+    /// `orientation` is the side of the pivot the wheel is on.
+    pub fn drop_pair(&mut self, pair: (Color, Color), column: usize,
+                     orientation: Direction) -> Result<(), Illegal> {
+        for (coord, color) in self.landing(pair, column, orientation)?.iter() {
+            self.cells[coord.y as usize][coord.x as usize] = *color;
+        }
+        Ok(())
+    }
+
+    /// Whether the spot new pairs spawn into is taken, ending the game.
+    pub fn spawn_blocked(&self) -> bool {
+        !self.is_empty(&DROP_POS)
+    }
+
+    /// Every spot a freshly spawned pair could be steered into.
+    pub fn legal_placements(&self) -> Vec<Placement> {
+        let spawn = Puyo::<Coord>::new(
+            DROP_POS, DROP_POS.apply_motion(Direction::Up));
+        if self.spawn_blocked() || !self.is_empty(spawn.wheel()) {
+            return Vec::new();
+        }
+        placement::reachable(self, &spawn)
+    }
+
+    /// Drop a pair, then let gravity and chains play out.
+    pub fn place(&mut self, pair: (Color, Color), column: usize,
+                 orientation: Direction) -> Result<ChainResult, Illegal> {
+        self.drop_pair(pair, column, orientation)?;
+        Ok(self.resolve_chains())
+    }
+
+    /// Drop up to `amount` garbage into the top of the board, one column at
+    /// a time, starting from `column_index` in the garbage column order.
+    ///
+    /// Returns how many were actually placed.
+    pub fn drop_garbage(&mut self, amount: u32, column_index: &mut usize)
+            -> u32 {
+        let mut full_columns = 0;
+        let mut amount_spawned = 0;
+        while amount_spawned < amount && full_columns < BOARD_WIDTH {
+            let x = GARBAGE_COLUMNS[*column_index];
+            for y in (0..BOARD_HEIGHT).rev(){
+                match self.cells[y][x] {
+                    Garbage => continue,
+                    Empty => {
+                        self.cells[y][x] = Garbage;
+                        amount_spawned += 1;
+                        break;
+                    }
+                    _ => {
+                        full_columns += 1;
+                        break;
+                    }
+                }
+            }
+            *column_index = (*column_index + 1) % GARBAGE_COLUMNS.len();
+        }
+        amount_spawned
+    }
+}
+
+/// One row of `Color::to_char` letters per line, top row first.
+impl<const W: usize> fmt::Display for Board<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.cells.iter().rev().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for color in row.iter() {
+                write!(f, "{}", color.to_char())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the `Display` layout back. Blank lines are skipped, and a layout
+/// with fewer rows than the board sits on the bottom of it.
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        if rows.len() > BOARD_HEIGHT {
+            return Err(ParseBoardError::TooManyRows);
+        }
+        let mut board = Board::new();
+        for (i, line) in rows.iter().enumerate() {
+            if line.chars().count() != BOARD_WIDTH {
+                return Err(ParseBoardError::BadRow(i));
+            }
+            let y = rows.len() - 1 - i;
+            for (x, letter) in line.chars().enumerate() {
+                let color = Color::from_char(letter)
+                    .ok_or(ParseBoardError::BadColor(letter))?;
+                board.cells[y][x] = color;
+            }
+        }
+        Ok(board)
+    }
+}
@@ -1,18 +1,56 @@
-use std::collections::HashSet;
-use std::cmp;
+use std::fmt;
 
 use super::color::Color;
 
 const POINTS_PER_GARBAGE: u32 = 70;
 
-#[derive(Debug)]
+/// The outcome of resolving a chain from start to finish.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ChainResult {
+    pub chains: u32,
+    pub cleared: u32,
+    pub score: u32,
+    pub garbage: u32,
+}
+
+/// How the score of a chain so far is worked out: ten points for every puyo
+/// cleared, times the sum of the bonuses kept between 1 and 999.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ScoreBreakdown {
+    pub cleared: u32,
+    pub chain_power: u32,
+    pub color_bonus: u32,
+    pub group_bonus: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn multiplier(&self) -> u32 {
+        let total = self.chain_power + self.color_bonus + self.group_bonus;
+        total.clamp(1, 999)
+    }
+
+    pub fn score(&self) -> u32 {
+        10 * self.cleared * self.multiplier()
+    }
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "10 x {} cleared x (chain {} + color {} + group {} = {}) = {}",
+               self.cleared, self.chain_power, self.color_bonus,
+               self.group_bonus, self.multiplier(), self.score())
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct ChainTracker {
     total_score: u32,
     total_cleared: u32,
     num_chains: u32,
-    colors: HashSet<Color>,
+    colors: u8,
     group_bonus: u32,
     leftover: u32,
+    popped: [u32; 7],
 }
 
 impl ChainTracker {
@@ -21,15 +59,21 @@ impl ChainTracker {
             total_score: 0,
             total_cleared: 0,
             num_chains: 0,
-            colors: HashSet::new(),
+            colors: 0,
             group_bonus: 0,
             leftover: 0,
+            popped: [0; 7],
         }
     }
 
-    pub fn record_group(&mut self, color: Color, num_puyo: u32){
+    /// Record a group of `colored` puyo of `color` popping, taking
+    /// `garbage` garbage puyo with it.
+    pub fn record_group(&mut self, color: Color, colored: u32, garbage: u32){
+        let num_puyo = colored + garbage;
+        self.popped[color as usize] += colored;
+        self.popped[Color::Garbage as usize] += garbage;
         self.total_cleared += num_puyo;
-        self.colors.insert(color);
+        self.colors |= 1 << (color as u8);
         let group_bonus = if num_puyo < 5 {
             0
         } else if num_puyo >= 11 {
@@ -44,6 +88,16 @@ impl ChainTracker {
         self.num_chains += 1;
     }
 
+    /// How many puyo of `color` have popped since this tracker was made,
+    /// across every chain.
+    pub fn popped(&self, color: Color) -> u32 {
+        self.popped[color as usize]
+    }
+
+    pub fn chains(&self) -> u32 {
+        self.num_chains
+    }
+
     pub fn score(&self) -> u32 {
         self.total_score + self.get_score()
     }
@@ -56,14 +110,27 @@ impl ChainTracker {
         let garbage = score / POINTS_PER_GARBAGE;
         self.total_cleared = 0;
         self.num_chains = 0;
-        self.colors.clear();
+        self.colors = 0;
         self.group_bonus = 0;
         self.leftover = score % POINTS_PER_GARBAGE;
         self.total_score += score;
         return garbage;
     }
 
-    fn get_score(&self) -> u32 {
+    pub fn finish(mut self) -> ChainResult {
+        let chains = self.num_chains;
+        let cleared = self.total_cleared;
+        let garbage = self.convert_to_garbage();
+        ChainResult {
+            chains,
+            cleared,
+            score: self.total_score,
+            garbage,
+        }
+    }
+
+    /// The bonuses making up the score since garbage was last worked out.
+    pub fn breakdown(&self) -> ScoreBreakdown {
         let chain_power = if self.num_chains <= 1 {
             0
         } else if self.num_chains >= 9 {
@@ -71,7 +138,7 @@ impl ChainTracker {
         } else {
             8 * (2u32).pow(self.num_chains - 2)
         };
-        let color_bonus = match self.colors.len() {
+        let color_bonus = match self.colors.count_ones() {
             0 => 0,
             1 => 0,
             2 => 3,
@@ -80,9 +147,15 @@ impl ChainTracker {
             5 => 24,
             _ => unreachable!(),
         };
-        let total_bonus = chain_power + color_bonus + self.group_bonus;
-        let total_bonus = cmp::max(total_bonus, 1);
-        let total_bonus = cmp::min(total_bonus, 999);
-        return 10 * self.total_cleared * total_bonus;
+        ScoreBreakdown {
+            cleared: self.total_cleared,
+            chain_power,
+            color_bonus,
+            group_bonus: self.group_bonus,
+        }
+    }
+
+    fn get_score(&self) -> u32 {
+        self.breakdown().score()
     }
 }
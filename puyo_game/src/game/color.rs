@@ -31,6 +31,33 @@ impl Color {
         NORMAL_COLORS[choice]
     }
 
+    /// The letter used for this color in text layouts: `.` for empty, `O`
+    /// for garbage and `R`, `G`, `B`, `Y`, `P` for the rest.
+    pub fn to_char(self) -> char {
+        match self {
+            Empty => '.',
+            Garbage => 'O',
+            Red => 'R',
+            Green => 'G',
+            Blue => 'B',
+            Yellow => 'Y',
+            Violet => 'P',
+        }
+    }
+
+    pub fn from_char(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            '.' => Some(Empty),
+            'O' => Some(Garbage),
+            'R' => Some(Red),
+            'G' => Some(Green),
+            'B' => Some(Blue),
+            'Y' => Some(Yellow),
+            'P' => Some(Violet),
+            _ => None,
+        }
+    }
+
     pub fn is_normal(&self) -> bool {
         match self {
             Empty | Garbage => false,
@@ -38,3 +65,20 @@ impl Color {
         }
     }
 }
+
+/// A pair as its two `Color::to_char` letters, pivot first, like `RG`.
+pub fn pair_to_string(pair: (Color, Color)) -> String {
+    [pair.0.to_char(), pair.1.to_char()].iter().collect()
+}
+
+/// Reads `pair_to_string` back. Both letters have to be normal colors.
+pub fn pair_from_str(text: &str) -> Option<(Color, Color)> {
+    let mut letters = text.chars().map(Color::from_char);
+    match (letters.next(), letters.next(), letters.next()) {
+        (Some(Some(pivot)), Some(Some(wheel)), None)
+                if pivot.is_normal() && wheel.is_normal() => {
+            Some((pivot, wheel))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use super::Game;
+use super::placement::Input;
+
+/// Anything that can play a `Game`: a person at a keyboard, a recording,
+/// the CPU or someone on the other end of a network connection.
+pub trait Controller {
+    /// The inputs to feed `game` this tick, in order.
+    fn poll(&mut self, game: &Game) -> Vec<Input>;
+
+    /// Accept an input that came from outside the engine, such as a key
+    /// press. Controllers that make their own decisions ignore it.
+    fn feed(&mut self, _input: Input) {}
+
+    /// Forget any state tied to the game being played, for when it is
+    /// replaced with a new one.
+    fn reset(&mut self) {}
+}
+
+/// Buffers key presses between ticks.
+#[derive(Default)]
+pub struct Keyboard {
+    pressed: Vec<Input>,
+}
+
+impl Keyboard {
+    pub fn new() -> Keyboard {
+        Keyboard::default()
+    }
+}
+
+impl Controller for Keyboard {
+    fn poll(&mut self, _game: &Game) -> Vec<Input> {
+        std::mem::take(&mut self.pressed)
+    }
+
+    fn feed(&mut self, input: Input) {
+        self.pressed.push(input);
+    }
+
+    fn reset(&mut self) {
+        self.pressed.clear();
+    }
+}
+
+/// Plays back (frame, input) pairs, such as those taken by a `Recorder`.
+///
+/// Replays only line up with a game started from the same seed.
+pub struct Replay {
+    inputs: Vec<(u32, Input)>,
+    position: usize,
+}
+
+impl Replay {
+    pub fn new(inputs: Vec<(u32, Input)>) -> Replay {
+        Replay { inputs, position: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.inputs.len()
+    }
+}
+
+impl Controller for Replay {
+    fn poll(&mut self, game: &Game) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        while let Some((frame, input)) = self.inputs.get(self.position) {
+            if *frame > game.frame() {
+                break;
+            }
+            inputs.push(*input);
+            self.position += 1;
+        }
+        inputs
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+}
+
+/// Passes another controller's input through, keeping a copy of it.
+pub struct Recorder<C> {
+    inner: C,
+    recording: Vec<(u32, Input)>,
+}
+
+impl<C: Controller> Recorder<C> {
+    pub fn new(inner: C) -> Recorder<C> {
+        Recorder { inner, recording: Vec::new() }
+    }
+
+    pub fn recording(&self) -> &[(u32, Input)] {
+        &self.recording
+    }
+
+    pub fn into_replay(self) -> Replay {
+        Replay::new(self.recording)
+    }
+}
+
+impl<C: Controller> Controller for Recorder<C> {
+    fn poll(&mut self, game: &Game) -> Vec<Input> {
+        let inputs = self.inner.poll(game);
+        let frame = game.frame();
+        self.recording.extend(inputs.iter().map(|input| (frame, *input)));
+        inputs
+    }
+
+    fn feed(&mut self, input: Input) {
+        self.inner.feed(input);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.recording.clear();
+    }
+}
+
+/// Input from a remote player, tagged with the frame it was made on.
+///
+/// Anything that arrives late is applied on the next tick rather than
+/// dropped.
+#[derive(Default)]
+pub struct Network {
+    received: VecDeque<(u32, Input)>,
+}
+
+impl Network {
+    pub fn new() -> Network {
+        Network::default()
+    }
+
+    pub fn receive(&mut self, frame: u32, input: Input) {
+        let index = self.received.iter()
+            .position(|(other, _)| *other > frame)
+            .unwrap_or(self.received.len());
+        self.received.insert(index, (frame, input));
+    }
+}
+
+impl Controller for Network {
+    fn poll(&mut self, game: &Game) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        while let Some((frame, _)) = self.received.front() {
+            if *frame > game.frame() {
+                break;
+            }
+            inputs.push(self.received.pop_front().unwrap().1);
+        }
+        inputs
+    }
+
+    fn feed(&mut self, input: Input) {
+        self.received.push_front((0, input));
+    }
+
+    fn reset(&mut self) {
+        self.received.clear();
+    }
+}
@@ -0,0 +1,437 @@
+//! Several players steering pairs on one wide board at the same time.
+//!
+//! The stack is a `Board` `W` columns wide, so gravity and chains work as
+//! they do in `Game`; this module only moves the pairs. Pairs move and
+//! rotate by the same rules as in `Game`, with the other players' pairs in
+//! the way as well as the stack.
+
+use std::cmp;
+use std::fmt;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use super::{merge_motion, Game, BOARD_WIDTH, BOARD_HEIGHT, DROP_INTERVAL, DROP_POS};
+use super::board::Board;
+use super::chain::ChainTracker;
+use super::color::Color;
+use super::controller::Controller;
+use super::placement::{self, Input};
+use super::puyo::*;
+use super::util::*;
+
+/// One player's pair, and where their pairs come in.
+#[derive(Clone)]
+struct Cursor {
+    spawn: Coord,
+    current: Option<Puyo<Coord>>,
+    colors: Puyo<Color>,
+    next: Puyo<Color>,
+    motion: Direction,
+    rotate: Rotation,
+    pairs_spawned: u32,
+}
+
+/// A board `W` columns wide, `BOARD_WIDTH` for every player, each of whom
+/// is played by a `Controller` and drops pairs in from the middle of their
+/// own stretch of it.
+///
+/// Everyone's pairs fall at once and block each other. A pair only locks
+/// when it lands on the stack or the floor; one resting on another pair
+/// waits for it to get out of the way. The stack falls and pops while pairs
+/// are still in the air, and everyone shares the score. The game is over
+/// when anyone's spawn is buried.
+///
+/// Controllers see a `Game` `BOARD_WIDTH` columns wide around their own
+/// pair, with everyone else's pairs in it as garbage.
+pub struct Coop<const W: usize> {
+    // not counting the pairs under control
+    stack: Board<W>,
+    cursors: Vec<Cursor>,
+    controllers: Vec<Box<dyn Controller>>,
+    // what each controller is shown, kept between ticks
+    views: Vec<Game>,
+    rng: StdRng,
+    excluded_color: Color,
+    chain: ChainTracker,
+    max_chain: u32,
+    tick_num: u32,
+    frame: u32,
+    is_over: bool,
+}
+
+impl<const W: usize> Coop<W> {
+    /// A game for one player per controller, which needs `W` to be
+    /// `BOARD_WIDTH` for each of them.
+    pub fn new(controllers: Vec<Box<dyn Controller>>) -> Coop<W> {
+        Coop::with_seed(rand::random(), controllers)
+    }
+
+    /// A game whose pairs come out in the same order every time for the
+    /// same `seed`. Players take turns drawing from the same sequence.
+    pub fn with_seed(seed: u64, controllers: Vec<Box<dyn Controller>>)
+            -> Coop<W> {
+        assert!(!controllers.is_empty());
+        assert_eq!(controllers.len() * BOARD_WIDTH, W);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let excluded_color = Color::Violet;
+        let views = vec![Game::with_pairs(Board::new(), &[]); controllers.len()];
+        let cursors = (0..controllers.len()).map(|player| Cursor {
+            spawn: Coord {
+                x: DROP_POS.x + (player * BOARD_WIDTH) as i32,
+                y: DROP_POS.y,
+            },
+            current: None,
+            colors: Puyo::empty(),
+            next: Puyo::from_excluded(&mut rng, excluded_color),
+            motion: Direction::None,
+            rotate: Rotation::None,
+            pairs_spawned: 0,
+        }).collect();
+        Coop {
+            stack: Board::default(),
+            cursors,
+            controllers,
+            views,
+            rng,
+            excluded_color,
+            chain: ChainTracker::new(),
+            max_chain: 0,
+            tick_num: DROP_INTERVAL,
+            frame: 0,
+            is_over: false,
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        self.cursors.len()
+    }
+
+    pub fn controller(&mut self, player: usize) -> &mut dyn Controller {
+        self.controllers[player].as_mut()
+    }
+
+    pub fn set_controller(&mut self, player: usize,
+                          controller: Box<dyn Controller>) {
+        self.controllers[player] = controller;
+    }
+
+    /// Start over with a fresh board, keeping the same controllers.
+    pub fn restart(&mut self) {
+        let controllers = std::mem::take(&mut self.controllers);
+        *self = Coop::with_seed(rand::random(), controllers);
+        for controller in self.controllers.iter_mut() {
+            controller.reset();
+        }
+    }
+
+    /// The board with every pair under control drawn in.
+    pub fn board(&self) -> Board<W> {
+        let mut board = self.stack;
+        for cursor in self.cursors.iter() {
+            if let Some(pos) = &cursor.current {
+                board.set(pos.pivot(), *cursor.colors.pivot());
+                board.set(pos.wheel(), *cursor.colors.wheel());
+            }
+        }
+        board
+    }
+
+    /// The board without the pairs under control.
+    pub fn stack(&self) -> &Board<W> {
+        &self.stack
+    }
+
+    /// The (pivot, wheel) colors of `player`'s pair, if they have one.
+    pub fn current_pair(&self, player: usize) -> Option<(Color, Color)> {
+        let cursor = &self.cursors[player];
+        cursor.current.as_ref()?;
+        Some((*cursor.colors.pivot(), *cursor.colors.wheel()))
+    }
+
+    pub fn next_pair(&self, player: usize) -> (Color, Color) {
+        let next = &self.cursors[player].next;
+        (*next.pivot(), *next.wheel())
+    }
+
+    // one tick's worth of input for `player`, as with `Game::input`
+    fn input(&mut self, player: usize, input: Input) {
+        let cursor = &mut self.cursors[player];
+        match input {
+            Input::Left => cursor.move_(Direction::Left),
+            Input::Right => cursor.move_(Direction::Right),
+            Input::Down => cursor.move_(Direction::Down),
+            Input::Up => cursor.move_(Direction::Up),
+            Input::Rotate => cursor.rotate(),
+            Input::DoubleRotate => {
+                cursor.rotate();
+                cursor.rotate();
+            }
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.chain.score()
+    }
+
+    /// The longest chain fired so far.
+    pub fn max_chain(&self) -> u32 {
+        self.max_chain
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.is_over
+    }
+
+    /// How many ticks this game has run for.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Feed every controller's input, then move the pairs and the stack.
+    pub fn tick(&mut self) {
+        if self.is_over {
+            return;
+        }
+        for player in 0..self.players() {
+            self.update_view(player);
+            for input in self.controllers[player].poll(&self.views[player]) {
+                self.input(player, input);
+            }
+        }
+        self.frame += 1;
+        self.tick_num += 1;
+        if self.tick_num > DROP_INTERVAL {
+            self.tick_num = 0;
+        }
+        for player in 0..self.players() {
+            self.steer(player);
+        }
+        if self.tick_num == 0 {
+            self.drop_pairs();
+        }
+        if (self.tick_num % 35) == 0 {
+            return;
+        }
+        let cursors = &self.cursors;
+        let held = |coord: &Coord| cursors.iter().any(|c| c.holds(coord));
+        if self.stack.step_gravity_around(held) {
+            return;
+        }
+        if self.stack.pop_groups(&mut self.chain) {
+            return;
+        }
+        self.max_chain = cmp::max(self.max_chain, self.chain.chains());
+        self.chain.convert_to_garbage();
+        for player in 0..self.players() {
+            self.spawn(player);
+        }
+    }
+
+    // show `player`'s controller the `BOARD_WIDTH` columns around their
+    // pair, or their own stretch of the board while they wait for one
+    fn update_view(&mut self, player: usize) {
+        let cursor = &self.cursors[player];
+        let last = (W - BOARD_WIDTH) as i32;
+        let offset = match &cursor.current {
+            Some(pos) => (pos.pivot().x - DROP_POS.x).clamp(0, last),
+            None => (player * BOARD_WIDTH) as i32,
+        };
+        let mut board = Board::new();
+        for y in 0..BOARD_HEIGHT as i32 {
+            for x in 0..BOARD_WIDTH as i32 {
+                let coord = Coord { x: x + offset, y };
+                let color = match self.holder(&coord) {
+                    Some(holder) if holder != player => Color::Garbage,
+                    _ => self.stack.get(&coord).unwrap_or(Color::Empty),
+                };
+                board.set(&Coord { x, y }, color);
+            }
+        }
+        let current = cursor.current.as_ref().map(|pos| {
+            let shift = |coord: &Coord| Coord { x: coord.x - offset, y: coord.y };
+            let pos = Puyo::<Coord>::new(shift(pos.pivot()), shift(pos.wheel()));
+            board.set(pos.pivot(), *cursor.colors.pivot());
+            board.set(pos.wheel(), *cursor.colors.wheel());
+            pos
+        });
+        self.views[player].show(board, current, &cursor.next,
+                                cursor.pairs_spawned, self.frame, self.is_over);
+    }
+
+    // whose pair is at `coord`, if anyone's
+    fn holder(&self, coord: &Coord) -> Option<usize> {
+        self.cursors.iter().position(|cursor| cursor.holds(coord))
+    }
+
+    // whether `player`'s pair could go in at `coord`
+    fn is_free(&self, coord: &Coord, player: usize) -> bool {
+        (self.stack.is_empty(coord)
+         && self.holder(coord).map_or(true, |holder| holder == player))
+    }
+
+    // move and rotate `player`'s pair as asked, if there is room
+    fn steer(&mut self, player: usize) {
+        let cursor = &self.cursors[player];
+        let (motion, rotation) = (cursor.motion, cursor.rotate);
+        let mut pos = match &cursor.current {
+            Some(pos) => pos.clone(),
+            None => return,
+        };
+        if motion != Direction::None {
+            let free = |coord: &Coord| self.is_free(coord, player);
+            if let Some(moved) = placement::try_move_where(free, &pos, motion) {
+                pos = moved;
+            }
+        }
+        if rotation != Rotation::None {
+            let free = |coord: &Coord| self.is_free(coord, player);
+            if let Some(rotated) = placement::try_rotate_where(
+                    free, &pos, rotation) {
+                pos = rotated;
+            }
+        }
+        let cursor = &mut self.cursors[player];
+        cursor.current = Some(pos);
+        cursor.motion = Direction::None;
+        cursor.rotate = Rotation::None;
+    }
+
+    // drop every pair a row, lowest first so pairs stacked on each other
+    // fall together
+    fn drop_pairs(&mut self) {
+        let mut order: Vec<(i32, usize)> = self.cursors.iter().enumerate()
+            .filter_map(|(player, cursor)| {
+                let pos = cursor.current.as_ref()?;
+                Some((cmp::min(pos.pivot().y, pos.wheel().y), player))
+            })
+            .collect();
+        order.sort();
+        for (_, player) in order {
+            self.drop_pair(player);
+        }
+    }
+
+    fn drop_pair(&mut self, player: usize) {
+        let pos = match &self.cursors[player].current {
+            Some(pos) => pos.clone(),
+            None => return,
+        };
+        let free = |coord: &Coord| self.is_free(coord, player);
+        if let Some(dropped) = placement::try_move_where(
+                free, &pos, Direction::Down) {
+            self.cursors[player].current = Some(dropped);
+            return;
+        }
+        let open = |coord: &Coord| self.stack.is_empty(coord);
+        if placement::try_move_where(open, &pos, Direction::Down).is_some() {
+            // resting on someone else's pair
+            return;
+        }
+        // puyo is no longer under user control
+        let cursor = &mut self.cursors[player];
+        cursor.current = None;
+        let colors = std::mem::replace(&mut cursor.colors, Puyo::empty());
+        self.stack.set(pos.pivot(), *colors.pivot());
+        self.stack.set(pos.wheel(), *colors.wheel());
+    }
+
+    // give `player` a new pair if they need one and there is room for it
+    fn spawn(&mut self, player: usize) {
+        let spawn = self.cursors[player].spawn;
+        if self.cursors[player].current.is_some() {
+            return;
+        }
+        if !self.stack.is_empty(&spawn) {
+            self.is_over = true;
+            return;
+        }
+        let pos = Puyo::<Coord>::new(spawn, spawn.apply_motion(Direction::Up));
+        if self.holder(pos.pivot()).is_some() || self.holder(pos.wheel()).is_some() {
+            // someone else's pair is in the way; try again next tick
+            return;
+        }
+        let next = Puyo::from_excluded(&mut self.rng, self.excluded_color);
+        let cursor = &mut self.cursors[player];
+        cursor.colors = std::mem::replace(&mut cursor.next, next);
+        cursor.current = Some(pos);
+        cursor.pairs_spawned += 1;
+    }
+}
+
+impl Cursor {
+    fn holds(&self, coord: &Coord) -> bool {
+        match &self.current {
+            Some(pos) => pos.pivot() == coord || pos.wheel() == coord,
+            None => false,
+        }
+    }
+
+    fn move_(&mut self, motion: Direction) {
+        self.motion = merge_motion(self.motion, motion);
+    }
+
+    fn rotate(&mut self) {
+        if self.current.is_some() {
+            self.rotate = match self.rotate {
+                Rotation::None => Rotation::Single,
+                _ => Rotation::Double,
+            }
+        }
+    }
+}
+
+/// Same as the `Board` layout, with the pairs under control drawn in.
+impl<const W: usize> fmt::Display for Coop<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.board().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{Cpu, Difficulty};
+    use crate::game::controller::Replay;
+
+    fn column(coop: &Coop<12>, x: usize) -> Vec<Color> {
+        coop.stack().column(x).take_while(|color| *color != Color::Empty)
+            .collect()
+    }
+
+    #[test]
+    fn replays_steer_each_players_pair() {
+        let one = Replay::new(vec![(5, Input::Left), (6, Input::Left)]);
+        let two = Replay::new(vec![(5, Input::Right)]);
+        let mut coop = Coop::<12>::with_seed(
+            3, vec![Box::new(one), Box::new(two)]);
+        let (one_pair, two_pair) = (coop.next_pair(0), coop.next_pair(1));
+        while coop.stack().rows().flatten().filter(|c| **c != Color::Empty)
+                .count() < 4 {
+            coop.tick();
+        }
+        assert_eq!(column(&coop, 1), vec![one_pair.0, one_pair.1]);
+        assert_eq!(column(&coop, 10), vec![two_pair.0, two_pair.1]);
+    }
+
+    #[test]
+    fn cpus_play_the_same_game_from_the_same_seeds() {
+        let play = || {
+            let controllers: Vec<Box<dyn Controller>> = (0..2)
+                .map(|player| {
+                    Box::new(Cpu::with_seed(Difficulty::Normal, player))
+                        as Box<dyn Controller>
+                })
+                .collect();
+            let mut coop = Coop::<12>::with_seed(5, controllers);
+            while !coop.is_over() && coop.frame() < 5000 {
+                coop.tick();
+            }
+            coop
+        };
+        let (first, second) = (play(), play());
+        assert!(first.score() > 0);
+        assert_eq!(first.score(), second.score());
+        assert_eq!(first.stack(), second.stack());
+    }
+}
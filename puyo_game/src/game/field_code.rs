@@ -0,0 +1,193 @@
+//! The compact field codes web chain simulators share positions with.
+//!
+//! A code is the field, then optionally `_` and the pairs, each written
+//! with the characters `0-9`, `a-z`, `A-Z`, `[` and `]` standing for 0 to
+//! 63. Cells are numbered 0 for empty, 1 to 5 for red, green, blue, yellow
+//! and violet and 6 for garbage.
+//!
+//! The field is read row by row from the top left, one character to every
+//! two cells, worth eight times the left cell plus the right one. Empty
+//! cells at the start are left off, so the field sits on the bottom of the
+//! board. Each pair takes two characters: the first is the pivot plus
+//! eight times the wheel, and the second says where it was placed, which
+//! this game doesn't use and writes as `0`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Board, BOARD_WIDTH, BOARD_HEIGHT};
+use super::color::Color;
+use super::util::Coord;
+
+const DIGITS: &[u8; 64] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ[]";
+const CELLS: [Color; 7] = [
+    Color::Empty,
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::Yellow,
+    Color::Violet,
+    Color::Garbage,
+];
+
+/// Why a string could not be read as a `FieldCode`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParseFieldCodeError {
+    BadDigit(char),
+    /// A digit standing for a cell this game doesn't have, like a wall.
+    BadCell(char),
+    BadPair(char),
+    /// The pairs ran out half way through one.
+    UnevenPairs,
+    TooManyCells,
+}
+
+impl fmt::Display for ParseFieldCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFieldCodeError::BadDigit(digit) => write!(
+                f, "{:?} is not a field code character", digit),
+            ParseFieldCodeError::BadCell(digit) => write!(
+                f, "{:?} holds a cell that isn't a puyo", digit),
+            ParseFieldCodeError::BadPair(digit) => write!(
+                f, "{:?} is not a pair of colors", digit),
+            ParseFieldCodeError::UnevenPairs => write!(
+                f, "the last pair is cut short"),
+            ParseFieldCodeError::TooManyCells => write!(
+                f, "more than {} cells", BOARD_WIDTH * BOARD_HEIGHT),
+        }
+    }
+}
+
+impl std::error::Error for ParseFieldCodeError {}
+
+/// A board and the pairs to come, as shared between simulators.
+///
+/// Reading accepts either the bare code or a whole simulator link ending
+/// in one, after its last `?` or `/`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FieldCode {
+    pub board: Board,
+    pub pairs: Vec<(Color, Color)>,
+}
+
+fn cell_number(color: Color) -> usize {
+    CELLS.iter().position(|cell| *cell == color).unwrap()
+}
+
+fn digit_value(digit: char) -> Result<usize, ParseFieldCodeError> {
+    DIGITS.iter()
+        .position(|d| *d as char == digit)
+        .ok_or(ParseFieldCodeError::BadDigit(digit))
+}
+
+impl fmt::Display for FieldCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<usize> = self.board.rows().rev()
+            .flat_map(|row| row.iter().map(|color| cell_number(*color)))
+            .collect();
+        let digits: Vec<usize> = cells.chunks(2)
+            .map(|two| two[0] * 8 + two[1])
+            .skip_while(|digit| *digit == 0)
+            .collect();
+        for digit in digits {
+            write!(f, "{}", DIGITS[digit] as char)?;
+        }
+        if !self.pairs.is_empty() {
+            write!(f, "_")?;
+            for (pivot, wheel) in self.pairs.iter() {
+                let digit = cell_number(*pivot) + 8 * cell_number(*wheel);
+                write!(f, "{}0", DIGITS[digit] as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FieldCode {
+    type Err = ParseFieldCodeError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let code = &text[text.rfind(['?', '/'])
+            .map_or(0, |i| i + 1)..];
+        let (field, pairs) = match code.find('_') {
+            Some(split) => (&code[..split], &code[split + 1..]),
+            None => (code, ""),
+        };
+        let field: Vec<char> = field.chars().collect();
+        if field.len() * 2 > BOARD_WIDTH * BOARD_HEIGHT {
+            return Err(ParseFieldCodeError::TooManyCells);
+        }
+        let mut board = Board::new();
+        // the last character holds the bottom right two cells
+        for (i, digit) in field.iter().rev().enumerate() {
+            let value = digit_value(*digit)?;
+            for (j, number) in [value % 8, value / 8].iter().enumerate() {
+                let color = *CELLS.get(*number)
+                    .ok_or(ParseFieldCodeError::BadCell(*digit))?;
+                let cell = i * 2 + j;
+                let coord = Coord {
+                    x: (BOARD_WIDTH - 1 - cell % BOARD_WIDTH) as i32,
+                    y: (cell / BOARD_WIDTH) as i32,
+                };
+                board.set(&coord, color);
+            }
+        }
+
+        let digits: Vec<char> = pairs.chars().collect();
+        let mut parsed = Vec::new();
+        for two in digits.chunks(2) {
+            if two.len() < 2 {
+                return Err(ParseFieldCodeError::UnevenPairs);
+            }
+            digit_value(two[1])?;
+            let value = digit_value(two[0])?;
+            let color = |number: usize| {
+                CELLS.get(number)
+                    .filter(|color| color.is_normal())
+                    .copied()
+                    .ok_or(ParseFieldCodeError::BadPair(two[0]))
+            };
+            parsed.push((color(value % 8)?, color(value / 8)?));
+        }
+        Ok(FieldCode { board, pairs: parsed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn random_codes_read_back() {
+        let mut rng = StdRng::seed_from_u64(44);
+        for _ in 0..5_000 {
+            let mut board = Board::new();
+            for y in 0..BOARD_HEIGHT {
+                for x in 0..BOARD_WIDTH {
+                    let coord = Coord { x: x as i32, y: y as i32 };
+                    board.set(&coord, *CELLS.choose(&mut rng).unwrap());
+                }
+            }
+            let pairs = (0..rng.gen_range(0, 8))
+                .map(|_| (Color::any(&mut rng), Color::any(&mut rng)))
+                .collect();
+            let code = FieldCode { board, pairs };
+            assert_eq!(code.to_string().parse(), Ok(code));
+        }
+    }
+
+    #[test]
+    fn codes_and_links_read_back() {
+        for text in ["", "4a", "10w1p2_a0j0", "SSSSSS", "_a0"] {
+            let code: FieldCode = text.parse().unwrap();
+            assert_eq!(code.to_string(), text);
+            let link = format!("https://example.com/simulator?{}", text);
+            assert_eq!(link.parse(), Ok(code));
+        }
+    }
+}
@@ -1,26 +1,52 @@
 #![allow(unused_parens)]
 
-use std::collections::HashSet;
+use std::cmp;
+use std::collections::VecDeque;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 
 pub mod color;
 pub mod util;
 mod puyo;
-mod chain;
+pub mod chain;
+pub mod board;
+pub mod bitboard;
+pub mod placement;
+pub mod controller;
+pub mod versus;
+pub mod battle;
+pub mod coop;
+pub mod puzzle;
+pub mod field_code;
+pub mod step;
 pub mod render;
 
 use color::Color;
-use color::Color::*;
 use util::*;
 use puyo::*;
 use chain::ChainTracker;
+pub use board::{Board, Illegal, ParseBoardError};
+pub use bitboard::BitBoard;
+pub use placement::{Placement, PlaceError, Input};
+pub use controller::Controller;
+pub use versus::{Versus, Outcome};
+pub use battle::{Battle, Targeting};
+pub use coop::Coop;
+pub use puzzle::{Puzzle, Goal, PuzzleState};
+pub use field_code::{FieldCode, ParseFieldCodeError};
+pub use chain::{ChainResult, ScoreBreakdown};
+pub use step::{ChainStep, ChainSteps};
 pub use render::Renderer;
 pub use util::Direction;
 
+#[derive(Clone)]
 pub struct Game {
     is_over: bool,
     tick_num: u32,
-    front_board: Vec<Vec<Color>>,
-    board: Vec<Vec<Color>>,
+    drop_interval: u32,
+    front_board: Board,
+    board: Board,
     excluded_color: Color,
     current: Option<Puyo<Coord>>,
     next: Puyo<Color>,
@@ -31,25 +57,88 @@ pub struct Game {
     rotate: Rotation,
     outgoing_garbage: u32,
     hold_garbage: bool,
+    pairs_spawned: u32,
+    frame: u32,
+    max_chain: u32,
+    garbage_sent: u32,
+    last_placement: Option<(usize, Direction)>,
+    dealer: Dealer,
+    out_of_pairs: bool,
+}
+
+/// Where new pairs come from.
+#[derive(Clone)]
+enum Dealer {
+    Random(Box<StdRng>),
+    /// A set list, after which no more pairs come.
+    Fixed(VecDeque<(Color, Color)>),
+    /// A set list, then random pairs.
+    Queued(VecDeque<(Color, Color)>, Box<StdRng>),
+}
+
+impl Dealer {
+    fn deal(&mut self, excluded_color: Color) -> Puyo<Color> {
+        match self {
+            Dealer::Random(rng) => Puyo::from_excluded(&mut **rng, excluded_color),
+            Dealer::Fixed(pairs) => match pairs.pop_front() {
+                Some((pivot, wheel)) => Puyo::<Color>::new(pivot, wheel),
+                None => Puyo::empty(),
+            },
+            Dealer::Queued(pairs, rng) => match pairs.pop_front() {
+                Some((pivot, wheel)) => Puyo::<Color>::new(pivot, wheel),
+                None => Puyo::from_excluded(&mut **rng, excluded_color),
+            },
+        }
+    }
 }
 
 pub const BOARD_WIDTH: usize = 6;
 pub const BOARD_HEIGHT: usize = 13;
-const DROP_POS: Coord = Coord { x: 3, y: 11 };
-const GARBAGE_COLUMNS: [usize; 6] = [0, 3, 2, 5, 1, 4];
+pub(crate) const DROP_POS: Coord = Coord { x: 3, y: 11 };
+// ticks between the pair under control falling a row, unless changed
+const DROP_INTERVAL: u32 = 50;
 
 impl Game {
     pub fn new() -> Game {
-        //let mut rng = rand::thread_rng();
+        Game::with_seed(rand::random())
+    }
+
+    /// A game whose pairs come out in the same order every time for the
+    /// same `seed`.
+    pub fn with_seed(seed: u64) -> Game {
+        Game::with_dealer(
+            Dealer::Random(Box::new(StdRng::seed_from_u64(seed))), Board::new())
+    }
+
+    /// A game that starts from `board` as it is, deals exactly `pairs`, in
+    /// order, then stops. Anything floating on `board` falls, and any
+    /// groups pop, on the first ticks.
+    pub fn with_pairs(board: Board, pairs: &[(Color, Color)]) -> Game {
+        Game::with_dealer(
+            Dealer::Fixed(pairs.iter().copied().collect()), board)
+    }
+
+    /// A game that starts from `board` as it is, deals `pairs` in order
+    /// and then random pairs from `seed`. Like `with_pairs`, `board` settles
+    /// on the first ticks.
+    pub fn with_queue(board: Board, pairs: &[(Color, Color)], seed: u64)
+            -> Game {
+        let rng = Box::new(StdRng::seed_from_u64(seed));
+        Game::with_dealer(
+            Dealer::Queued(pairs.iter().copied().collect(), rng), board)
+    }
+
+    fn with_dealer(mut dealer: Dealer, board: Board) -> Game {
         let excluded_color = Color::Violet;
         Game {
             is_over: false,
-            tick_num: 50,
-            front_board: vec![vec![excluded_color; BOARD_WIDTH]; BOARD_HEIGHT],
-            board: vec![vec![Empty; BOARD_WIDTH]; BOARD_HEIGHT],
+            tick_num: DROP_INTERVAL,
+            drop_interval: DROP_INTERVAL,
+            front_board: Board::filled(excluded_color),
+            board,
             excluded_color,
             current: None,
-            next: Puyo::<Color>::from_excluded(excluded_color),
+            next: dealer.deal(excluded_color),
             chain: ChainTracker::new(),
             incoming_garbage: 0,
             garbage_column_index: 0,
@@ -57,39 +146,59 @@ impl Game {
             rotate: Rotation::None,
             outgoing_garbage: 0,
             hold_garbage: false,
+            pairs_spawned: 0,
+            frame: 0,
+            max_chain: 0,
+            garbage_sent: 0,
+            last_placement: None,
+            dealer,
+            out_of_pairs: false,
         }
     }
 
+    /// Turn this game into a still picture of one played somewhere else,
+    /// like one player's part of a `Coop` board, to show a `Controller`.
+    /// `board` has `current` drawn in.
+    pub(crate) fn show(&mut self, board: Board, current: Option<Puyo<Coord>>,
+                       next: &Puyo<Color>, pairs_spawned: u32, frame: u32,
+                       is_over: bool) {
+        self.board = board;
+        self.current = current;
+        self.next = next.clone();
+        self.pairs_spawned = pairs_spawned;
+        self.frame = frame;
+        self.is_over = is_over;
+    }
+
     pub fn is_over(&self) -> bool {
         self.is_over
     }
 
+    /// Whether a game from `with_pairs` has settled after its last pair,
+    /// with nothing left to deal.
+    pub fn is_out_of_pairs(&self) -> bool {
+        self.out_of_pairs
+    }
+
     pub fn next_puyo(&self) -> (Color, Color) {
         (*self.next.pivot(), *self.next.wheel())
     }
 
+    /// The (pivot, wheel) colors of the pair under control, if any.
+    pub fn current_pair(&self) -> Option<(Color, Color)> {
+        let current = self.current.as_ref()?;
+        Some((self.board.get(current.pivot())?,
+              self.board.get(current.wheel())?))
+    }
+
+    /// How many pairs have spawned so far, which also identifies the pair
+    /// currently under control.
+    pub fn pair_number(&self) -> u32 {
+        self.pairs_spawned
+    }
+
     pub fn move_(&mut self, motion: Direction){
-        self.motion = match (self.motion, motion) {
-            (Direction::Up, _) => unreachable!(),
-            // same == same
-            (Direction::Left, Direction::Left) => Direction::Left,
-            (Direction::Right, Direction::Right) => Direction::Right,
-            (Direction::Down, Direction::Down) => Direction::Down,
-            // down & up = none
-            (Direction::Down, Direction::Up) => Direction::None,
-            // ignore other ups
-            (existing, Direction::Up) => existing,
-            // main case, if we were nothing, do something
-            (Direction::None, something) => something,
-            // None is a non-op
-            (existing, Direction::None) => existing,
-            // down has lower priority than left and right
-            (existing, Direction::Down) => existing,
-            (Direction::Down, something) => something,
-            // opposites cancel out
-            (Direction::Left, Direction::Right) => Direction::None,
-            (Direction::Right, Direction::Left) => Direction::None,
-        };
+        self.motion = merge_motion(self.motion, motion);
     }
 
     pub fn rotate(&mut self){
@@ -101,6 +210,70 @@ impl Game {
         }
     }
 
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The board with the pair under control lifted out of it.
+    pub fn stack(&self) -> Board {
+        let mut board = self.board;
+        if let Some(current) = &self.current {
+            board.set(current.pivot(), Color::Empty);
+            board.set(current.wheel(), Color::Empty);
+        }
+        board
+    }
+
+    /// Feed one tick's worth of input, as a player pressing keys would.
+    pub fn input(&mut self, input: Input){
+        match input {
+            Input::Left => self.move_(Direction::Left),
+            Input::Right => self.move_(Direction::Right),
+            Input::Down => self.move_(Direction::Down),
+            Input::Up => self.move_(Direction::Up),
+            Input::Rotate => self.rotate(),
+            Input::DoubleRotate => {
+                self.rotate();
+                self.rotate();
+            }
+        }
+    }
+
+    /// Every spot the pair under control can still be steered into.
+    pub fn legal_placements(&self) -> Vec<Placement> {
+        match &self.current {
+            Some(current) => placement::reachable(&self.stack(), current),
+            None => Vec::new(),
+        }
+    }
+
+    /// Put the pair under control straight into its resting spot, as if it
+    /// had been steered there and dropped. Gravity, chains and the next
+    /// pair follow on the usual ticks.
+    pub fn place(&mut self, column: usize, orientation: Direction)
+            -> Result<(), PlaceError> {
+        let pair = self.current_pair().ok_or(PlaceError::NoPair)?;
+        let reachable = self.legal_placements().iter().any(|placement| {
+            placement.column == column && placement.orientation == orientation
+        });
+        if !reachable {
+            return Err(PlaceError::Unreachable);
+        }
+        let mut stack = self.stack();
+        stack.drop_pair(pair, column, orientation)?;
+        self.board = stack;
+        self.current = None;
+        self.last_placement = Some((column, orientation));
+        self.motion = Direction::None;
+        self.rotate = Rotation::None;
+        Ok(())
+    }
+
+    /// The (column, orientation) the most recent pair locked in at.
+    pub fn last_placement(&self) -> Option<(usize, Direction)> {
+        self.last_placement
+    }
+
     pub fn pending_garbage(&self) -> u32 {
         self.incoming_garbage
     }
@@ -109,22 +282,70 @@ impl Game {
         self.chain.score()
     }
 
+    /// The longest chain fired so far.
+    pub fn max_chain(&self) -> u32 {
+        self.max_chain
+    }
+
+    /// How many puyo of `color` have popped so far.
+    pub fn popped(&self, color: Color) -> u32 {
+        self.chain.popped(color)
+    }
+
+    /// Garbage sent to the opponent so far, after offsetting.
+    pub fn garbage_sent(&self) -> u32 {
+        self.garbage_sent
+    }
+
     pub fn add_garbage(&mut self, amount: u32){
         self.incoming_garbage += amount;
     }
 
+    /// Take up to `amount` off the garbage waiting to fall, returning how
+    /// much came off.
+    pub fn cancel_garbage(&mut self, amount: u32) -> u32 {
+        let cancelled = cmp::min(amount, self.incoming_garbage);
+        self.incoming_garbage -= cancelled;
+        cancelled
+    }
+
     pub fn get_garbage(&mut self) -> u32 {
         let value = self.outgoing_garbage;
         self.outgoing_garbage = 0;
         return value;
     }
 
+    /// How many ticks the pair under control waits between falling a row.
+    pub fn drop_interval(&self) -> u32 {
+        self.drop_interval
+    }
+
+    /// Speed the pair under control up or slow it down; `ticks` is at
+    /// least 1.
+    pub fn set_drop_interval(&mut self, ticks: u32) {
+        self.drop_interval = cmp::max(ticks, 1);
+    }
+
+    /// How many ticks this game has run for.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Feed this tick's input from `controller`, then tick.
+    pub fn tick_with(&mut self, controller: &mut dyn Controller){
+        for input in controller.poll(self) {
+            self.input(input);
+        }
+        self.tick();
+    }
+
     pub fn tick(&mut self){
         if self.is_over {
             return;
         }
+        self.frame += 1;
         self.tick_num += 1;
-        if self.tick_num > 50 {
+        if self.tick_num > self.drop_interval {
             self.tick_num = 0;
         }
         if self.check_motion() {
@@ -161,28 +382,11 @@ impl Game {
     }
 
     pub fn finish_render(&mut self){
-        let zipper = self.front_board.iter_mut().zip(self.board.iter_mut());
-        for (front_row, back_row) in zipper {
-            front_row.copy_from_slice(&back_row);
-        }
-    }
-
-    fn is_empty(&self, coord: &Coord) -> bool {
-        match self.board.get(coord.y as usize) {
-            None => false,
-            Some(row) => match row.get(coord.x as usize) {
-                Some(&Empty) => true,
-                _ => false,
-            }
-        }
+        self.front_board = self.board;
     }
 
     fn swap_color(&mut self, coord: &Coord, color: Color) -> Color {
-        let row = self.board.get_mut(coord.y as usize).unwrap();
-        let item = row.get_mut(coord.x as usize).unwrap();
-        let orig = *item;
-        *item = color;
-        orig
+        self.board.set(coord, color).unwrap_or(Color::Empty)
     }
 
     fn swap_puyo(&mut self, pos: &Puyo<Coord>, colors: Puyo<Color>)
@@ -193,18 +397,20 @@ impl Game {
         )
     }
 
-    fn check_new_pos(&mut self, mut pos: Puyo<Coord>, colors: Puyo<Color>)
-            -> bool {
-        let mut valid = true;
-
-        if !(self.is_empty(pos.pivot()) && self.is_empty(pos.wheel())) {
-            valid = false;
-            pos = self.current.clone().unwrap();
-        }
-
-        self.swap_puyo(&pos, colors);
+    fn update_current<F>(&mut self, update: F) -> bool
+            where F: FnOnce(&Board, &Puyo<Coord>) -> Option<Puyo<Coord>> {
+        let puyo_pos = match &self.current {
+            Some(current) => current.clone(),
+            None => return false,
+        };
+        let puyo_colors = self.swap_puyo(&puyo_pos, Puyo::empty());
+        let (valid, pos) = match update(&self.board, &puyo_pos) {
+            Some(pos) => (true, pos),
+            None => (false, puyo_pos),
+        };
+        self.swap_puyo(&pos, puyo_colors);
         self.current = Some(pos);
-        return valid;
+        valid
     }
 
     fn check_motion(&mut self) -> bool {
@@ -213,177 +419,57 @@ impl Game {
         }
         let motion = self.motion;
         self.motion = Direction::None;
-        let mut puyo_pos = match &self.current {
-            Some(current) => current.clone(),
-            None => return false,
-        };
-        let puyo_colors = self.swap_puyo(&puyo_pos, Puyo::empty());
-        puyo_pos.move_(motion);
-        return self.check_new_pos(puyo_pos, puyo_colors);
+        self.update_current(|board, pos| {
+            placement::try_move(board, pos, motion)
+        })
     }
 
     fn check_rotation(&mut self) -> bool {
         if let Rotation::None = self.rotate {
             return false;
         }
-
-        let mut puyo_pos = match &self.current {
-            Some(current) => current.clone(),
-            None => return false,
-        };
-
-        let puyo_colors = self.swap_puyo(&puyo_pos, Puyo::empty());
-
-        if let Rotation::Double = self.rotate {
-            if puyo_pos.is_vertical() {
-                puyo_pos.flip();
-            } else {
-                self.rotate = Rotation::Single;
-            }
-        }
-
-        if let Rotation::Single = self.rotate {
-            puyo_pos.rotate();
-        }
-
-        let mut valid = true;
-
-        if self.is_empty(puyo_pos.wheel()){
-            // free rotate
-        } else if puyo_pos.is_vertical() {
-            // floor kick
-            puyo_pos.move_(Direction::Up);
-        } else {
-            // wall kick
-            match puyo_pos.rotation() {
-                Direction::Left => puyo_pos.move_(Direction::Right),
-                Direction::Right => puyo_pos.move_(Direction::Left),
-                _ => unreachable!(),
-            }
-            if !self.is_empty(&puyo_pos.pivot()) {
-                // rotation denied
-                valid = false;
-                puyo_pos = self.current.clone().unwrap();
-            }
+        if self.current.is_none() {
+            return false;
         }
-        self.rotate = Rotation::None;
-        self.swap_puyo(&puyo_pos, puyo_colors);
-        self.current = Some(puyo_pos);
-        return valid;
+        let rotation = std::mem::replace(&mut self.rotate, Rotation::None);
+        self.update_current(|board, pos| {
+            placement::try_rotate(board, pos, rotation)
+        })
     }
 
     fn check_drop(&mut self, full: bool) -> bool {
-        let mut puyo_pos = match &self.current {
-            Some(current) => current.clone(),
-            None => return false,
-        };
+        if self.current.is_none() {
+            return false;
+        }
 
         if !full {
             return true;
         }
 
-        let puyo_colors = self.swap_puyo(&puyo_pos, Puyo::empty());
-        
-        puyo_pos.move_(Direction::Down);
-
-        let valid = self.check_new_pos(puyo_pos, puyo_colors);
+        let valid = self.update_current(|board, pos| {
+            placement::try_move(board, pos, Direction::Down)
+        });
         if !valid {
             // puyo is no longer under user control
-            self.current = None;
+            if let Some(current) = self.current.take() {
+                self.last_placement = Some(
+                    (current.pivot().x as usize, current.rotation()));
+            }
         }
         return valid;
     }
 
     fn check_gravity(&mut self) -> bool {
-        let mut did_something = false;
-
-        for above_index in 1..self.board.len() {
-            let (lower_rows, upper_rows) = (
-                self.board.split_at_mut(above_index));
-            let row = lower_rows.last_mut().unwrap();
-            let above = upper_rows.first_mut().unwrap();
-            for (item, item_above) in row.iter_mut().zip(above.iter_mut()) {
-                if *item == Empty && *item_above != Empty {
-                    *item = *item_above;
-                    *item_above = Empty;
-                    did_something = true;
-                }
-            }
-        }
-        return did_something;
+        self.board.step_gravity()
     }
 
     fn check_chains(&mut self) -> bool {
-        let mut not_part_of_chain: HashSet<Coord> = HashSet::new();
-        let mut found_coords;
-        let mut pending_coords = HashSet::new();
-        let mut garbage_coords;
-        let mut any_cleared = false;
-
-        for y in 0i32..((BOARD_HEIGHT - 1) as i32) {
-            for x in 0i32..(BOARD_WIDTH as i32) {
-                found_coords = HashSet::new();
-                garbage_coords = HashSet::new();
-                let coord = Coord { x, y };
-                if not_part_of_chain.contains(&coord) {
-                    continue;
-                }
-                let blob_color = match self.board[y as usize][x as usize] {
-                    color if color.is_normal() => color,
-                    _ => continue,
-                };
-                pending_coords.insert(coord);
-                while let Some(item) = pending_coords.iter().next() {
-                    let item = item.clone();
-                    pending_coords.remove(&item);
-                    let color = self.board[item.y as usize][item.x as usize];
-                    if color == Garbage {
-                        garbage_coords.insert(item);
-                        continue;
-                    } else if color != blob_color {
-                        continue;
-                    }
-                    for dir in Direction::each_real() {
-                        let possible = item.apply_motion(*dir);
-                        if (not_part_of_chain.contains(&possible)
-                            || found_coords.contains(&possible)
-                            || possible.x < 0
-                            || possible.y < 0
-                            || possible.x >= BOARD_WIDTH as i32
-                            || possible.y >= (BOARD_HEIGHT - 1) as i32){
-                            continue;
-                        }
-                        pending_coords.insert(possible);
-                    }
-                    found_coords.insert(item);
-                }
-                if found_coords.len() < 4 {
-                    not_part_of_chain.extend(found_coords);
-                } else {
-                    any_cleared = true;
-                    let mut group_num = 0;
-                    for puyo in found_coords {
-                        self.swap_color(&puyo, Empty);
-                        group_num += 1;
-                    }
-                    for puyo in garbage_coords {
-                        self.swap_color(&puyo, Empty);
-                        group_num += 1;
-                    }
-                    self.chain.record_group(blob_color, group_num);
-                }
-            }
-        }
-
-        if any_cleared {
-            self.chain.end_cycle();
-        }
-        
-        return any_cleared;
+        self.board.pop_groups(&mut self.chain)
     }
 
     fn apply_score(&mut self) -> bool {
         //print!("\x1b[20;2HChain: {:?}", self.chain);
+        self.max_chain = cmp::max(self.max_chain, self.chain.chains());
         let mut garbage = self.chain.convert_to_garbage();
         if self.incoming_garbage < garbage {
             garbage -= self.incoming_garbage;
@@ -393,6 +479,7 @@ impl Game {
             garbage = 0;
         }
         self.outgoing_garbage += garbage;
+        self.garbage_sent += garbage;
 
         return false;
     }
@@ -402,30 +489,10 @@ impl Game {
             self.hold_garbage = false;
             return false;
         }
-        let mut full_columns = 0;
-        let mut amount_spawned = 0;
-        while (self.incoming_garbage > 0
-               && amount_spawned < 30
-               && full_columns < BOARD_WIDTH) {
-            let x = GARBAGE_COLUMNS[self.garbage_column_index];
-            for y in (0..BOARD_HEIGHT).rev(){
-                match self.board[y][x] {
-                    Garbage => continue,
-                    Empty => {
-                        self.board[y][x] = Garbage;
-                        self.incoming_garbage -= 1;
-                        amount_spawned += 1;
-                        break;
-                    }
-                    _ => {
-                        full_columns += 1;
-                        break;
-                    }
-                }
-            }
-            self.garbage_column_index = (
-                (self.garbage_column_index + 1) % GARBAGE_COLUMNS.len());
-        }
+        let amount = cmp::min(self.incoming_garbage, 30);
+        let amount_spawned = self.board.drop_garbage(
+            amount, &mut self.garbage_column_index);
+        self.incoming_garbage -= amount_spawned;
         if amount_spawned > 29 {
             self.hold_garbage = true;
         }
@@ -433,16 +500,154 @@ impl Game {
     }
 
     fn spawn_puyo(&mut self) -> bool {
-        if self.board[DROP_POS.y as usize][DROP_POS.x as usize] != Empty {
+        if self.board.spawn_blocked() {
             self.is_over = true;
             return true;
         }
+        if *self.next.pivot() == Color::Empty {
+            self.out_of_pairs = true;
+            return false;
+        }
         let colors = std::mem::replace(
-            &mut self.next, Puyo::from_excluded(self.excluded_color));
+            &mut self.next, self.dealer.deal(self.excluded_color));
         let pos = Puyo::<Coord>::new(
             DROP_POS, DROP_POS.apply_motion(Direction::Up));
         self.swap_puyo(&pos, colors);
         self.current = Some(pos);
+        self.pairs_spawned += 1;
         return true;
     }
 }
+
+// the motion to make this tick, from what was asked for so far and `motion`
+fn merge_motion(existing: Direction, motion: Direction) -> Direction {
+    match (existing, motion) {
+        (Direction::Up, _) => unreachable!(),
+        // same == same
+        (Direction::Left, Direction::Left) => Direction::Left,
+        (Direction::Right, Direction::Right) => Direction::Right,
+        (Direction::Down, Direction::Down) => Direction::Down,
+        // down & up = none
+        (Direction::Down, Direction::Up) => Direction::None,
+        // ignore other ups
+        (existing, Direction::Up) => existing,
+        // main case, if we were nothing, do something
+        (Direction::None, something) => something,
+        // None is a non-op
+        (existing, Direction::None) => existing,
+        // down has lower priority than left and right
+        (existing, Direction::Down) => existing,
+        (Direction::Down, something) => something,
+        // opposites cancel out
+        (Direction::Left, Direction::Right) => Direction::None,
+        (Direction::Right, Direction::Left) => Direction::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flipped_pair_locks_facing_down() {
+        let mut game = Game::with_pairs(Board::new(),
+                                        &[(Color::Red, Color::Blue)]);
+        while game.current_pair().is_none() {
+            game.tick();
+        }
+        game.input(Input::DoubleRotate);
+        while game.last_placement().is_none() {
+            game.tick();
+        }
+        assert_eq!(game.last_placement(), Some((3, Direction::Down)));
+        assert_eq!(game.board().get(&Coord { x: 3, y: 1 }), Some(Color::Red));
+        assert_eq!(game.board().get(&Coord { x: 3, y: 0 }), Some(Color::Blue));
+    }
+
+    #[test]
+    fn boards_settle_on_the_first_ticks() {
+        let mut board = Board::new();
+        board.set(&Coord { x: 0, y: 3 }, Color::Red);
+        for mut game in [Game::with_pairs(board, &[]),
+                         Game::with_queue(board, &[], 1)] {
+            assert_eq!(*game.board(), board);
+            for _ in 0..10 {
+                game.tick();
+            }
+            assert_eq!(game.board().get(&Coord { x: 0, y: 3 }),
+                       Some(Color::Empty));
+            assert_eq!(game.board().get(&Coord { x: 0, y: 0 }),
+                       Some(Color::Red));
+        }
+    }
+
+    fn spawned(mut game: Game) -> Game {
+        while game.current_pair().is_none() {
+            game.tick();
+        }
+        game
+    }
+
+    // tick until the pair under control has locked and the next one is in
+    fn next_pair(game: &mut Game, input: Option<Input>) {
+        let pair = game.pair_number();
+        while game.pair_number() == pair {
+            if let Some(input) = input {
+                game.input(input);
+            }
+            game.tick();
+        }
+    }
+
+    #[test]
+    fn place_refuses_what_the_pair_cant_reach() {
+        let game = Game::with_pairs(Board::new(), &[(Color::Red, Color::Blue)]);
+        assert_eq!(game.clone().place(3, Direction::Up),
+                   Err(PlaceError::NoPair));
+
+        // a wall in column 2 shuts off the two columns left of it
+        let mut board = Board::new();
+        for y in 0..12 {
+            board.set(&Coord { x: 2, y }, Color::Garbage);
+        }
+        let mut game = spawned(Game::with_pairs(
+            board, &[(Color::Red, Color::Blue)]));
+        let before = (*game.board(), game.current_pair(),
+                      game.legal_placements(), game.last_placement());
+        for (column, orientation) in [(0, Direction::Up), (1, Direction::Right),
+                                      (6, Direction::Up)] {
+            assert_eq!(game.place(column, orientation),
+                       Err(PlaceError::Unreachable));
+            let after = (*game.board(), game.current_pair(),
+                         game.legal_placements(), game.last_placement());
+            assert_eq!(after, before);
+        }
+    }
+
+    #[test]
+    fn place_matches_steering_the_pair_there() {
+        let board: Board = "R.....\nRG..B.\nGGBYBB".parse().unwrap();
+        let pairs = [(Color::Red, Color::Yellow), (Color::Blue, Color::Green)];
+        let game = spawned(Game::with_queue(board, &pairs, 3));
+        let placements = game.legal_placements();
+        assert!(placements.len() > 10);
+        for placement in placements {
+            let mut placed = game.clone();
+            placed.place(placement.column, placement.orientation).unwrap();
+            next_pair(&mut placed, None);
+
+            let mut steered = game.clone();
+            for input in placement.inputs.iter() {
+                steered.input(*input);
+                steered.tick();
+            }
+            next_pair(&mut steered, Some(Input::Down));
+
+            assert_eq!(placed.board(), steered.board());
+            assert_eq!(placed.score(), steered.score());
+            assert_eq!(placed.last_placement(),
+                       Some((placement.column, placement.orientation)));
+            assert_eq!(steered.last_placement(), placed.last_placement());
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+use super::board::{Board, Illegal};
+use super::puyo::*;
+use super::util::*;
+use super::{BOARD_WIDTH, BOARD_HEIGHT};
+
+/// One tick's worth of input to a `Game`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Input {
+    Left,
+    Right,
+    Down,
+    /// Letting go of down, which cancels a down pressed in the same tick.
+    Up,
+    Rotate,
+    /// Pressing rotate twice in the same tick, which flips a vertical pair.
+    DoubleRotate,
+}
+
+static ALL_INPUTS: [Input; 5] = [
+    Input::Left,
+    Input::Right,
+    Input::Down,
+    Input::Rotate,
+    Input::DoubleRotate,
+];
+
+static ALL_ORIENTATIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+/// A final resting spot for a pair, and the inputs that get it there.
+///
+/// `orientation` is the side of the pivot the wheel ends up on; after
+/// `inputs` the pair can be left to fall the rest of the way.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Placement {
+    pub column: usize,
+    pub orientation: Direction,
+    pub inputs: Vec<Input>,
+}
+
+/// Why `Game::place` could not put the pair under control somewhere.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlaceError {
+    /// There is no pair under control to place.
+    NoPair,
+    /// The pair under control can't be steered there from where it is.
+    Unreachable,
+    /// The spot is no good on the board itself.
+    Illegal(Illegal),
+}
+
+impl From<Illegal> for PlaceError {
+    fn from(illegal: Illegal) -> Self {
+        PlaceError::Illegal(illegal)
+    }
+}
+
+fn orientation_index(orientation: Direction) -> usize {
+    ALL_ORIENTATIONS.iter().position(|d| *d == orientation).unwrap()
+}
+
+/// Where `pos` ends up after moving once in `motion`, if there is room.
+///
+/// `board` must not contain the pair itself.
+pub(crate) fn try_move(board: &Board, pos: &Puyo<Coord>, motion: Direction)
+        -> Option<Puyo<Coord>> {
+    try_move_where(|coord| board.is_empty(coord), pos, motion)
+}
+
+/// Same as `try_move`, with `is_free` saying which cells the pair can go in.
+pub(crate) fn try_move_where<F>(is_free: F, pos: &Puyo<Coord>,
+                                motion: Direction) -> Option<Puyo<Coord>>
+        where F: Fn(&Coord) -> bool {
+    let mut pos = pos.clone();
+    pos.move_(motion);
+    if is_free(pos.pivot()) && is_free(pos.wheel()) {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+/// Where `pos` ends up after rotating, including any floor or wall kick,
+/// if the rotation is allowed at all.
+///
+/// `board` must not contain the pair itself.
+pub(crate) fn try_rotate(board: &Board, pos: &Puyo<Coord>, rotation: Rotation)
+        -> Option<Puyo<Coord>> {
+    try_rotate_where(|coord| board.is_empty(coord), pos, rotation)
+}
+
+/// Same as `try_rotate`, with `is_free` saying which cells the pair can go
+/// in.
+pub(crate) fn try_rotate_where<F>(is_free: F, pos: &Puyo<Coord>,
+                                  rotation: Rotation) -> Option<Puyo<Coord>>
+        where F: Fn(&Coord) -> bool {
+    let mut pos = pos.clone();
+    let mut rotation = rotation;
+
+    if let Rotation::Double = rotation {
+        if pos.is_vertical() {
+            pos.flip();
+        } else {
+            rotation = Rotation::Single;
+        }
+    }
+
+    if let Rotation::Single = rotation {
+        pos.rotate();
+    }
+
+    if is_free(pos.wheel()) {
+        // free rotate
+        return Some(pos);
+    }
+    if pos.is_vertical() {
+        // floor kick
+        pos.move_(Direction::Up);
+    } else {
+        // wall kick
+        match pos.rotation() {
+            Direction::Left => pos.move_(Direction::Right),
+            Direction::Right => pos.move_(Direction::Left),
+            _ => unreachable!(),
+        }
+    }
+    if is_free(pos.pivot()) {
+        Some(pos)
+    } else {
+        // rotation denied
+        None
+    }
+}
+
+fn apply(board: &Board, pos: &Puyo<Coord>, input: Input)
+        -> Option<Puyo<Coord>> {
+    match input {
+        Input::Left => try_move(board, pos, Direction::Left),
+        Input::Right => try_move(board, pos, Direction::Right),
+        Input::Down => try_move(board, pos, Direction::Down),
+        Input::Up => None,
+        Input::Rotate => try_rotate(board, pos, Rotation::Single),
+        Input::DoubleRotate => try_rotate(board, pos, Rotation::Double),
+    }
+}
+
+/// Every (column, orientation) a pair at `start` can be steered into,
+/// each with the shortest input sequence that reaches it.
+///
+/// `board` must not contain the pair itself.
+pub(crate) fn reachable(board: &Board, start: &Puyo<Coord>) -> Vec<Placement> {
+    let key = |pos: &Puyo<Coord>| (
+        pos.pivot().y as usize,
+        pos.pivot().x as usize,
+        orientation_index(pos.rotation()),
+    );
+    let mut visited = [[[false; 4]; BOARD_WIDTH]; BOARD_HEIGHT];
+    let mut found = [[false; 4]; BOARD_WIDTH];
+    // every state reached so far, with the index of the state it came from
+    let mut states: Vec<(Puyo<Coord>, usize, Option<Input>)> = Vec::new();
+    let mut pending = VecDeque::new();
+    let mut placements = Vec::new();
+
+    let (y, x, o) = key(start);
+    visited[y][x][o] = true;
+    states.push((start.clone(), 0, None));
+    pending.push_back(0);
+
+    while let Some(index) = pending.pop_front() {
+        let pos = states[index].0.clone();
+        let (_, x, o) = key(&pos);
+        if !found[x][o] {
+            found[x][o] = true;
+            let mut inputs = Vec::new();
+            let mut step = index;
+            while let (_, parent, Some(input)) = &states[step] {
+                inputs.push(*input);
+                step = *parent;
+            }
+            inputs.reverse();
+            placements.push(Placement {
+                column: x,
+                orientation: ALL_ORIENTATIONS[o],
+                inputs,
+            });
+        }
+        for input in ALL_INPUTS.iter() {
+            if let Some(next) = apply(board, &pos, *input) {
+                let (y, x, o) = key(&next);
+                if !visited[y][x][o] {
+                    visited[y][x][o] = true;
+                    states.push((next, index, Some(*input)));
+                    pending.push_back(states.len() - 1);
+                }
+            }
+        }
+    }
+
+    placements.sort_by_key(|p| (p.column, orientation_index(p.orientation)));
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color::Color;
+    use super::*;
+
+    #[test]
+    fn floor_kick_stays_on_the_board() {
+        // a horizontal pair in the top row turning its wheel up has nowhere
+        // to kick to; kicking anyway would push the pivot off the board
+        let pos = Puyo::<Coord>::new(Coord { x: 3, y: 12 },
+                                     Coord { x: 2, y: 12 });
+        assert!(try_rotate(&Board::new(), &pos, Rotation::Single).is_none());
+    }
+
+    #[test]
+    fn floor_kick_moves_up_when_there_is_room() {
+        let mut board = Board::new();
+        board.set(&Coord { x: 3, y: 0 }, Color::Red);
+        let pos = Puyo::<Coord>::new(Coord { x: 3, y: 1 },
+                                     Coord { x: 4, y: 1 });
+        let kicked = try_rotate(&board, &pos, Rotation::Single).unwrap();
+        assert_eq!(*kicked.pivot(), Coord { x: 3, y: 2 });
+        assert_eq!(*kicked.wheel(), Coord { x: 3, y: 1 });
+    }
+}
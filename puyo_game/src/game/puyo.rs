@@ -1,3 +1,5 @@
+use rand::Rng;
+
 use super::color::*;
 use super::util::*;
 
@@ -22,11 +24,11 @@ impl Puyo<Color> {
         Puyo { pivot, wheel }
     }
 
-    pub fn from_excluded(excluded_color: Color) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn from_excluded<R: Rng + ?Sized>(rng: &mut R, excluded_color: Color)
+            -> Self {
         Puyo {
-            pivot: Color::exclude(&mut rng, excluded_color),
-            wheel: Color::exclude(&mut rng, excluded_color),
+            pivot: Color::exclude(rng, excluded_color),
+            wheel: Color::exclude(rng, excluded_color),
         }
     }
 
@@ -0,0 +1,279 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Board, Game, ParseBoardError};
+use super::chain::ChainTracker;
+use super::color::{pair_from_str, pair_to_string, Color};
+
+/// What a puzzle asks for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Goal {
+    /// Leave the board completely empty.
+    ClearAll,
+    /// Fire a chain at least this long.
+    Chain(u32),
+    /// Pop at least `count` puyo of `color`, over any number of chains.
+    Pop { color: Color, count: u32 },
+    /// Leave no garbage on the board.
+    ClearGarbage,
+}
+
+impl Goal {
+    pub fn is_met(&self, game: &Game) -> bool {
+        self.is_met_by(&game.stack(), game.max_chain(), |c| game.popped(c))
+    }
+
+    /// Same as `is_met`, for a board and tallies kept outside a `Game`.
+    pub fn is_met_by<F>(&self, stack: &Board, max_chain: u32, popped: F)
+            -> bool where F: Fn(Color) -> u32 {
+        match self {
+            Goal::ClearAll => stack.rows().flatten().all(|c| *c == Color::Empty),
+            Goal::Chain(chains) => max_chain >= *chains,
+            Goal::Pop { color, count } => popped(*color) >= *count,
+            Goal::ClearGarbage => {
+                stack.rows().flatten().all(|c| *c != Color::Garbage)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::ClearAll => write!(f, "clear all"),
+            Goal::Chain(chains) => write!(f, "chain {}", chains),
+            Goal::Pop { color, count } => {
+                write!(f, "pop {} {}", count, color.to_char())
+            }
+            Goal::ClearGarbage => write!(f, "clear garbage"),
+        }
+    }
+}
+
+impl FromStr for Goal {
+    type Err = ParsePuzzleError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || ParsePuzzleError::BadGoal(text.to_string());
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words[..] {
+            ["clear", "all"] => Ok(Goal::ClearAll),
+            ["clear", "garbage"] => Ok(Goal::ClearGarbage),
+            ["chain", chains] => {
+                Ok(Goal::Chain(chains.parse().map_err(|_| bad())?))
+            }
+            ["pop", count, color] => {
+                let mut letters = color.chars();
+                let color = match (letters.next(), letters.next()) {
+                    (Some(letter), None) => Color::from_char(letter),
+                    _ => None,
+                };
+                Ok(Goal::Pop {
+                    color: color.filter(|c| c.is_normal()).ok_or_else(bad)?,
+                    count: count.parse().map_err(|_| bad())?,
+                })
+            }
+            _ => Err(bad()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PuzzleState {
+    Playing,
+    Solved,
+    Failed,
+}
+
+/// Why a text layout could not be read as a `Puzzle`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParsePuzzleError {
+    /// A line before `board:` that isn't `key: value`, or has an unknown
+    /// key.
+    BadLine(String),
+    BadGoal(String),
+    BadPair(String),
+    MissingGoal,
+    /// The goal is met before any pair is placed, like `clear garbage`
+    /// on a board with no garbage.
+    AlreadyMet,
+    Board(ParseBoardError),
+}
+
+impl fmt::Display for ParsePuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePuzzleError::BadLine(line) => {
+                write!(f, "don't know what {:?} means", line)
+            }
+            ParsePuzzleError::BadGoal(goal) => {
+                write!(f, "{:?} is not a goal", goal)
+            }
+            ParsePuzzleError::BadPair(pair) => {
+                write!(f, "{:?} is not a pair", pair)
+            }
+            ParsePuzzleError::MissingGoal => write!(f, "no goal given"),
+            ParsePuzzleError::AlreadyMet => {
+                write!(f, "the goal is met before any pair is placed")
+            }
+            ParsePuzzleError::Board(err) => write!(f, "bad board: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParsePuzzleError {}
+
+impl From<ParseBoardError> for ParsePuzzleError {
+    fn from(err: ParseBoardError) -> Self {
+        ParsePuzzleError::Board(err)
+    }
+}
+
+/// A fixed board, a fixed list of pairs and a goal to reach with them.
+///
+/// In text, a puzzle is a few `key: value` lines followed by the board:
+///
+/// ```text
+/// name: First steps
+/// goal: chain 2
+/// pairs: RR GR
+/// board:
+/// ......
+/// G.....
+/// GGR...
+/// ```
+///
+/// Goals are `clear all`, `clear garbage`, `chain N` and `pop N X` with
+/// `X` a color letter. Pairs are pivot then wheel, and the board is in
+/// the same layout `Board` reads.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    pub goal: Goal,
+    pub pairs: Vec<(Color, Color)>,
+    pub board: Board,
+}
+
+impl Puzzle {
+    pub fn start(&self) -> Game {
+        Game::with_pairs(self.board, &self.pairs)
+    }
+
+    /// Solved as soon as the goal is met; failed once the game tops out or
+    /// settles with no pairs left.
+    pub fn state(&self, game: &Game) -> PuzzleState {
+        if self.goal.is_met(game) {
+            PuzzleState::Solved
+        } else if game.is_over() || game.is_out_of_pairs() {
+            PuzzleState::Failed
+        } else {
+            PuzzleState::Playing
+        }
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name: {}", self.name)?;
+        writeln!(f, "goal: {}", self.goal)?;
+        let pairs: Vec<String> = self.pairs.iter()
+            .map(|pair| pair_to_string(*pair))
+            .collect();
+        writeln!(f, "pairs: {}", pairs.join(" "))?;
+        writeln!(f, "board:")?;
+        write!(f, "{}", self.board)
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = ParsePuzzleError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut name = String::new();
+        let mut goal = None;
+        let mut pairs = Vec::new();
+        let mut lines = text.lines();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad = || ParsePuzzleError::BadLine(line.to_string());
+            let (key, value) = match line.find(':') {
+                Some(colon) => (&line[..colon], line[colon + 1..].trim()),
+                None => return Err(bad()),
+            };
+            match key.trim() {
+                "name" => name = value.to_string(),
+                "goal" => goal = Some(value.parse()?),
+                "pairs" => pairs = value.split_whitespace()
+                    .map(parse_pair)
+                    .collect::<Result<_, _>>()?,
+                "board" => break,
+                _ => return Err(bad()),
+            }
+        }
+        let board: Board = lines.collect::<Vec<_>>().join("\n").parse()?;
+        let goal: Goal = goal.ok_or(ParsePuzzleError::MissingGoal)?;
+        // what the board does on its own, before the first pair lands
+        let mut start = board;
+        let mut chain = ChainTracker::new();
+        loop {
+            start.settle();
+            if !start.pop_groups(&mut chain) {
+                break;
+            }
+        }
+        if goal.is_met_by(&start, chain.chains(), |c| chain.popped(c)) {
+            return Err(ParsePuzzleError::AlreadyMet);
+        }
+        Ok(Puzzle {
+            name,
+            goal,
+            pairs,
+            board,
+        })
+    }
+}
+
+fn parse_pair(text: &str) -> Result<(Color, Color), ParsePuzzleError> {
+    pair_from_str(text)
+        .ok_or_else(|| ParsePuzzleError::BadPair(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goals_met_from_the_start_are_rejected() {
+        let no_garbage = "goal: clear garbage\npairs: RR\nboard:\nRG....";
+        assert_eq!(no_garbage.parse::<Puzzle>(),
+                   Err(ParsePuzzleError::AlreadyMet));
+        let empty = "goal: clear all\npairs: RR\nboard:\n......";
+        assert_eq!(empty.parse::<Puzzle>(), Err(ParsePuzzleError::AlreadyMet));
+        let no_chain = "goal: chain 0\npairs: RR\nboard:\nRG....";
+        assert_eq!(no_chain.parse::<Puzzle>(),
+                   Err(ParsePuzzleError::AlreadyMet));
+    }
+
+    #[test]
+    fn chains_already_on_the_board_meet_the_goal() {
+        let chain = "goal: chain 1\npairs: GB\nboard:\nRRRR..";
+        assert_eq!(chain.parse::<Puzzle>(), Err(ParsePuzzleError::AlreadyMet));
+    }
+
+    #[test]
+    fn pops_already_on_the_board_meet_the_goal() {
+        let pop = "goal: pop 4 R\npairs: GB\nboard:\nRRRR..";
+        assert_eq!(pop.parse::<Puzzle>(), Err(ParsePuzzleError::AlreadyMet));
+    }
+
+    #[test]
+    fn goals_still_to_reach_are_read() {
+        let dig = "goal: clear garbage\npairs: RG RG\nboard:\nOO....\nRR..GG";
+        let puzzle: Puzzle = dig.parse().unwrap();
+        assert_eq!(puzzle.goal, Goal::ClearGarbage);
+        assert_eq!(puzzle.state(&puzzle.start()), PuzzleState::Playing);
+    }
+}
@@ -1,5 +1,7 @@
 use super::util::*;
 use super::color::Color;
+use super::board::Board;
+use super::BOARD_WIDTH;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum RenderCommand {
@@ -12,8 +14,8 @@ use RenderCommand::*;
 const GOTO_THRESHOLD: usize = 2;
 
 pub struct Renderer<'a> {
-    front: &'a Vec<Vec<Color>>,
-    back: &'a Vec<Vec<Color>>,
+    front: &'a [[Color; BOARD_WIDTH]],
+    back: &'a [[Color; BOARD_WIDTH]],
     row: usize,
     col: usize,
     paint_current: bool,
@@ -29,8 +31,9 @@ enum AdvanceResult {
 }
 
 impl<'b> Renderer<'b> {
-    pub fn new<'a>(front: &'a Vec<Vec<Color>>, back: &'a Vec<Vec<Color>>) -> Renderer<'a> {
-        assert_eq!(front.len(), back.len());
+    pub fn new<'a>(front: &'a Board, back: &'a Board) -> Renderer<'a> {
+        let front = front.rows().as_slice();
+        let back = back.rows().as_slice();
         Renderer {
             front,
             back,
@@ -0,0 +1,116 @@
+//! Playing a chain out one link at a time, to see how it scores.
+
+use super::{Board, BOARD_WIDTH, BOARD_HEIGHT};
+use super::chain::{ChainResult, ChainTracker, ScoreBreakdown};
+use super::color::Color;
+use super::util::{Coord, Direction};
+
+/// One link of a chain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChainStep {
+    /// Counting from 1.
+    pub number: u32,
+    /// Whether anything fell before the link popped.
+    pub fell: bool,
+    /// The board once everything had fallen.
+    pub settled: Board,
+    /// Each group of one color that popped, with its cells.
+    pub groups: Vec<(Color, Vec<Coord>)>,
+    /// Garbage cleared by popping next to the groups.
+    pub garbage: Vec<Coord>,
+    /// The score of the chain up to and including this link.
+    pub breakdown: ScoreBreakdown,
+    /// The board with the popped cells gone, before anything falls again.
+    pub after: Board,
+}
+
+/// Steps through the chain a board sets off, the same way a game resolves
+/// it between pairs but without waiting on any frames.
+pub struct ChainSteps {
+    board: Board,
+    chain: ChainTracker,
+}
+
+impl ChainSteps {
+    pub fn new(board: Board) -> ChainSteps {
+        ChainSteps {
+            board,
+            chain: ChainTracker::new(),
+        }
+    }
+
+    /// The board as it is now; once every step is taken, how it ends up.
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    /// Play out whatever is left and total the chain up.
+    pub fn finish(mut self) -> (Board, ChainResult) {
+        while self.next().is_some() {}
+        self.board.settle();
+        (self.board, self.chain.finish())
+    }
+}
+
+impl Iterator for ChainSteps {
+    type Item = ChainStep;
+
+    fn next(&mut self) -> Option<ChainStep> {
+        let fell = self.board.settle();
+        let settled = self.board;
+        if !self.board.pop_groups(&mut self.chain) {
+            return None;
+        }
+        let after = self.board;
+        let (groups, garbage) = popped(&settled, &after);
+        Some(ChainStep {
+            number: self.chain.chains(),
+            fell,
+            settled,
+            groups,
+            garbage,
+            breakdown: self.chain.breakdown(),
+            after,
+        })
+    }
+}
+
+// the groups and garbage that were on `before` but are gone from `after`
+fn popped(before: &Board, after: &Board) -> (Vec<(Color, Vec<Coord>)>,
+                                               Vec<Coord>) {
+    let mut gone = Vec::new();
+    for y in 0..BOARD_HEIGHT {
+        for x in 0..BOARD_WIDTH {
+            let coord = Coord { x: x as i32, y: y as i32 };
+            if before.get(&coord) != after.get(&coord) {
+                gone.push((coord, before.get(&coord).unwrap()));
+            }
+        }
+    }
+    let garbage = gone.iter()
+        .filter(|(_, color)| *color == Color::Garbage)
+        .map(|(coord, _)| *coord)
+        .collect();
+    let mut groups = Vec::new();
+    let mut left: Vec<(Coord, Color)> = gone.into_iter()
+        .filter(|(_, color)| *color != Color::Garbage)
+        .collect();
+    while let Some(start) = left.pop() {
+        let mut group = vec![start.0];
+        let mut i = 0;
+        while i < group.len() {
+            let coord = group[i];
+            for dir in Direction::each_real() {
+                let next = coord.apply_motion(*dir);
+                if let Some(at) = left.iter()
+                        .position(|item| *item == (next, start.1)) {
+                    group.push(left.swap_remove(at).0);
+                }
+            }
+            i += 1;
+        }
+        groups.push((start.1, group));
+    }
+    groups.reverse();
+    (groups, garbage)
+}
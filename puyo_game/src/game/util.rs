@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Direction {
     None, Left, Right, Up, Down,
 }
@@ -16,7 +16,7 @@ impl Direction {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Coord {
     pub x: i32,
     pub y: i32,
@@ -65,15 +65,31 @@ impl Coord {
             (-1, 0) => Direction::Left,
             (1, 0) => Direction::Right,
             (0, 1) => Direction::Up,
-            (0, -1) => Direction::Up,
+            (0, -1) => Direction::Down,
             _ => Direction::None,
         }
     }
 }
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum Rotation {
     None,
     Single,
     Double,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motion_to_each_neighbor() {
+        let origin = Coord { x: 0, y: 0 };
+        assert_eq!(origin.motion_to(&Coord { x: -1, y: 0 }), Direction::Left);
+        assert_eq!(origin.motion_to(&Coord { x: 1, y: 0 }), Direction::Right);
+        assert_eq!(origin.motion_to(&Coord { x: 0, y: 1 }), Direction::Up);
+        assert_eq!(origin.motion_to(&Coord { x: 0, y: -1 }), Direction::Down);
+        assert_eq!(origin.motion_to(&Coord { x: 1, y: 1 }), Direction::None);
+    }
+}
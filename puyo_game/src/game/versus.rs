@@ -0,0 +1,70 @@
+use super::Game;
+use super::battle::Battle;
+use super::controller::Controller;
+use super::placement::Input;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The last one standing: a player's index, or in a `Battle` with
+    /// teams, a team number.
+    Winner(usize),
+    /// Everyone left topped out on the same tick.
+    Draw,
+}
+
+/// Two games sending garbage to each other, each played by a `Controller`.
+///
+/// Every input is recorded, so feeding the recordings back through
+/// `Replay`s on a `Versus` with the same seed plays the same match.
+pub struct Versus {
+    battle: Battle,
+}
+
+impl Versus {
+    pub fn new(one: Box<dyn Controller>, two: Box<dyn Controller>) -> Versus {
+        Versus::with_seed(rand::random(), one, two)
+    }
+
+    /// Like `new`, but both games deal the same pairs in the same order,
+    /// fixed by `seed`.
+    pub fn with_seed(seed: u64, one: Box<dyn Controller>,
+                     two: Box<dyn Controller>) -> Versus {
+        Versus { battle: Battle::with_seed(seed, vec![one, two]) }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.battle.seed()
+    }
+
+    /// Every (frame, input) `player` has made so far.
+    pub fn recording(&self, player: usize) -> &[(u32, Input)] {
+        self.battle.recording(player)
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        self.battle.game(player)
+    }
+
+    pub fn controller(&mut self, player: usize) -> &mut dyn Controller {
+        self.battle.controller(player)
+    }
+
+    pub fn set_controller(&mut self, player: usize,
+                          controller: Box<dyn Controller>) {
+        self.battle.set_controller(player, controller);
+    }
+
+    /// Start over with fresh games, keeping the same controllers.
+    pub fn restart(&mut self) {
+        self.battle.restart();
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.battle.outcome()
+    }
+
+    /// Tick both games and pass garbage between them, until one is over.
+    pub fn tick(&mut self) -> Option<Outcome> {
+        self.battle.tick()
+    }
+}
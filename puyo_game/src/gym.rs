@@ -0,0 +1,280 @@
+//! A gym-style environment around `Game` for training agents.
+//!
+//! Each step places one whole pair: the action picks a column and an
+//! orientation, the pair is put straight there with `Game::place`, and
+//! the game runs until the next one spawns. Everything is driven by the seed
+//! given to `reset`, so the same seed and actions always give the same
+//! observations and rewards.
+
+use std::thread;
+
+use crate::game::{BitBoard, Direction, Game, Placement};
+use crate::game::{BOARD_HEIGHT, BOARD_WIDTH};
+use crate::game::color::Color;
+
+const ORIENTATIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+const QUEUE_COLORS: [Color; 5] = [
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+    Color::Yellow,
+    Color::Violet,
+];
+
+/// Action `i` puts the pivot in column `i / 4` with the wheel on side
+/// `i % 4` of it, counting clockwise from up.
+pub const ACTIONS: usize = BOARD_WIDTH * 4;
+/// One plane per `Color`, including empty and garbage.
+pub const PLANES: usize = 7;
+/// The pair under control and the next pair, pivot then wheel.
+pub const QUEUE_PUYO: usize = 4;
+pub const OBSERVATION_SIZE: usize =
+    PLANES * BOARD_HEIGHT * BOARD_WIDTH + QUEUE_PUYO * QUEUE_COLORS.len() + 1;
+
+pub fn action(column: usize, orientation: Direction) -> Option<usize> {
+    let index = ORIENTATIONS.iter().position(|o| *o == orientation)?;
+    if column < BOARD_WIDTH {
+        Some(column * 4 + index)
+    } else {
+        None
+    }
+}
+
+pub fn column_and_orientation(action: usize) -> Option<(usize, Direction)> {
+    if action < ACTIONS {
+        Some((action / 4, ORIENTATIONS[action % 4]))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Observation {
+    /// `planes[color as usize][y][x]` is 1 where the stack has that color,
+    /// with row 0 at the bottom.
+    pub planes: [[[f32; BOARD_WIDTH]; BOARD_HEIGHT]; PLANES],
+    /// One-hot over red, green, blue, yellow and violet.
+    pub queue: [[f32; 5]; QUEUE_PUYO],
+    pub pending_garbage: f32,
+}
+
+impl Observation {
+    fn new(game: &Game) -> Observation {
+        let mut observation = Observation {
+            planes: [[[0.0; BOARD_WIDTH]; BOARD_HEIGHT]; PLANES],
+            queue: [[0.0; 5]; QUEUE_PUYO],
+            pending_garbage: game.pending_garbage() as f32,
+        };
+        for (y, row) in game.stack().rows().enumerate() {
+            for (x, color) in row.iter().enumerate() {
+                observation.planes[*color as usize][y][x] = 1.0;
+            }
+        }
+        let current = game.current_pair()
+            .unwrap_or((Color::Empty, Color::Empty));
+        let next = game.next_puyo();
+        let puyo = [current.0, current.1, next.0, next.1];
+        for (slot, color) in observation.queue.iter_mut().zip(puyo.iter()) {
+            if let Some(index) = QUEUE_COLORS.iter().position(|c| c == color) {
+                slot[index] = 1.0;
+            }
+        }
+        observation
+    }
+
+    /// Everything as one flat vector of `OBSERVATION_SIZE` values: the
+    /// planes, then the queue, then the garbage count.
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut values = Vec::with_capacity(OBSERVATION_SIZE);
+        for plane in self.planes.iter() {
+            for row in plane.iter() {
+                values.extend_from_slice(row);
+            }
+        }
+        for slot in self.queue.iter() {
+            values.extend_from_slice(slot);
+        }
+        values.push(self.pending_garbage);
+        values
+    }
+}
+
+/// What each step's reward is made of; every term is added up.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RewardConfig {
+    /// Per point of score gained.
+    pub score: f32,
+    /// Per link of the chain the placement set off.
+    pub chain: f32,
+    /// For every pair placed without topping out.
+    pub survival: f32,
+    /// Once, on the step the game ends.
+    pub game_over: f32,
+    /// For an action that isn't reachable; the game doesn't move.
+    pub invalid_action: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        RewardConfig {
+            score: 1.0 / 70.0,
+            chain: 0.0,
+            survival: 0.0,
+            game_over: -1.0,
+            invalid_action: -0.1,
+        }
+    }
+}
+
+pub struct Env {
+    game: Game,
+    rewards: RewardConfig,
+}
+
+impl Env {
+    /// An environment that still needs a `reset` before it is stepped.
+    pub fn new(rewards: RewardConfig) -> Env {
+        Env {
+            game: Game::with_seed(0),
+            rewards,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Start a new game, returning its first observation.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::with_seed(seed);
+        while !self.game.is_over() && self.game.current_pair().is_none() {
+            self.game.tick();
+        }
+        Observation::new(&self.game)
+    }
+
+    pub fn observation(&self) -> Observation {
+        Observation::new(&self.game)
+    }
+
+    /// Which actions are reachable for the pair under control.
+    pub fn legal_actions(&self) -> [bool; ACTIONS] {
+        let mut legal = [false; ACTIONS];
+        for placement in self.game.legal_placements() {
+            if let Some(index) = action(placement.column,
+                                        placement.orientation) {
+                legal[index] = true;
+            }
+        }
+        legal
+    }
+
+    /// Queue garbage for the next pair to land on, as an opponent would.
+    pub fn add_garbage(&mut self, amount: u32) {
+        self.game.add_garbage(amount);
+    }
+
+    pub fn step(&mut self, action: usize) -> (Observation, f32, bool) {
+        if self.game.is_over() {
+            return (self.observation(), 0.0, true);
+        }
+        let placement = match self.find(action) {
+            Some(placement) => placement,
+            None => {
+                return (self.observation(), self.rewards.invalid_action,
+                        false);
+            }
+        };
+        let chains = match self.game.current_pair() {
+            Some(pair) => BitBoard::from(&self.game.stack())
+                .place(pair, placement.column, placement.orientation)
+                .map_or(0, |result| result.chains),
+            None => 0,
+        };
+        let score = self.game.score();
+        let pair_number = self.game.pair_number();
+        self.game.place(placement.column, placement.orientation)
+            .expect("placement was reachable");
+        while !self.game.is_over() && self.game.pair_number() == pair_number {
+            self.game.tick();
+        }
+
+        let done = self.game.is_over();
+        let mut reward = self.rewards.score
+            * (self.game.score() - score) as f32
+            + self.rewards.chain * chains as f32;
+        reward += if done {
+            self.rewards.game_over
+        } else {
+            self.rewards.survival
+        };
+        (self.observation(), reward, done)
+    }
+
+    fn find(&self, action: usize) -> Option<Placement> {
+        let (column, orientation) = column_and_orientation(action)?;
+        self.game.legal_placements().into_iter().find(|placement| {
+            placement.column == column && placement.orientation == orientation
+        })
+    }
+}
+
+/// Many environments stepped together, spread over the available cores.
+pub struct VecEnv {
+    envs: Vec<Env>,
+}
+
+impl VecEnv {
+    pub fn new(count: usize, rewards: RewardConfig) -> VecEnv {
+        VecEnv {
+            envs: (0..count).map(|_| Env::new(rewards)).collect(),
+        }
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    pub fn envs_mut(&mut self) -> &mut [Env] {
+        &mut self.envs
+    }
+
+    /// Reset environment `i` with `seed + i`.
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        self.envs.iter_mut().enumerate()
+            .map(|(i, env)| env.reset(seed.wrapping_add(i as u64)))
+            .collect()
+    }
+
+    /// Step every environment with its own action. Finished environments
+    /// keep reporting `done` until they are reset.
+    pub fn step(&mut self, actions: &[usize])
+            -> Vec<(Observation, f32, bool)> {
+        assert_eq!(actions.len(), self.envs.len());
+        let mut results = Vec::with_capacity(self.envs.len());
+        if self.envs.is_empty() {
+            return results;
+        }
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = (self.envs.len() + threads - 1) / threads;
+        thread::scope(|scope| {
+            let workers: Vec<_> = self.envs.chunks_mut(chunk)
+                .zip(actions.chunks(chunk))
+                .map(|(envs, actions)| scope.spawn(move || {
+                    envs.iter_mut().zip(actions.iter())
+                        .map(|(env, action)| env.step(*action))
+                        .collect::<Vec<_>>()
+                }))
+                .collect();
+            for worker in workers {
+                results.extend(worker.join().unwrap());
+            }
+        });
+        results
+    }
+}
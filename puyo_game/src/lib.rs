@@ -1,3 +1,10 @@
 extern crate rand;
 
 pub mod game;
+pub mod ai;
+#[cfg(feature = "native")]
+pub mod bot;
+#[cfg(feature = "native")]
+pub mod tournament;
+#[cfg(feature = "native")]
+pub mod gym;
@@ -0,0 +1,220 @@
+//! Seeded round-robin matches between bots, with nothing drawn on screen.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::ai::{Cpu, Difficulty};
+use crate::bot::ExternalBot;
+use crate::game::{Controller, Outcome, Versus};
+
+const INITIAL_RATING: f64 = 1500.0;
+const RATING_K: f64 = 32.0;
+
+/// Someone taking part in a tournament.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Entrant {
+    Cpu(Difficulty),
+    /// A bot program speaking the `bot` protocol, as a command line.
+    External(String),
+}
+
+impl Entrant {
+    /// A fresh controller for one game, with any randomness fixed by `seed`.
+    pub fn controller(&self, seed: u64, time_limit: Duration)
+            -> io::Result<Box<dyn Controller>> {
+        Ok(match self {
+            Entrant::Cpu(difficulty) => {
+                Box::new(Cpu::with_seed(*difficulty, seed))
+            }
+            Entrant::External(command) => {
+                Box::new(ExternalBot::spawn(command, time_limit)?)
+            }
+        })
+    }
+}
+
+/// `cpu:easy`, `cpu:normal` and `cpu:hard` are the built-in players;
+/// anything else is taken as the command line of an external bot.
+impl FromStr for Entrant {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "cpu:easy" => Ok(Entrant::Cpu(Difficulty::Easy)),
+            "cpu:normal" => Ok(Entrant::Cpu(Difficulty::Normal)),
+            "cpu:hard" => Ok(Entrant::Cpu(Difficulty::Hard)),
+            _ if text.starts_with("cpu:") => {
+                Err(format!("unknown difficulty {:?}", &text[4..]))
+            }
+            _ if text.trim().is_empty() => Err("empty bot command".into()),
+            _ => Ok(Entrant::External(text.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entrant::Cpu(Difficulty::Easy) => write!(f, "cpu:easy"),
+            Entrant::Cpu(Difficulty::Normal) => write!(f, "cpu:normal"),
+            Entrant::Cpu(Difficulty::Hard) => write!(f, "cpu:hard"),
+            Entrant::External(command) => write!(f, "{}", command),
+        }
+    }
+}
+
+/// What happened in one game, indexed by side.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MatchResult {
+    /// `None` when the frame limit ran out first.
+    pub outcome: Option<Outcome>,
+    pub frames: u32,
+    pub scores: [u32; 2],
+    pub max_chains: [u32; 2],
+    pub garbage_sent: [u32; 2],
+}
+
+/// Play one game between two controllers, both dealt the pairs fixed by
+/// `seed`, for at most `max_frames` ticks.
+pub fn play_match(one: Box<dyn Controller>, two: Box<dyn Controller>,
+                  seed: u64, max_frames: u32) -> MatchResult {
+    let mut versus = Versus::with_seed(seed, one, two);
+    let mut outcome = None;
+    while outcome.is_none() && versus.game(0).frame() < max_frames {
+        outcome = versus.tick();
+    }
+    let (one, two) = (versus.game(0), versus.game(1));
+    MatchResult {
+        outcome,
+        frames: one.frame(),
+        scores: [one.score(), two.score()],
+        max_chains: [one.max_chain(), two.max_chain()],
+        garbage_sent: [one.garbage_sent(), two.garbage_sent()],
+    }
+}
+
+/// One entrant's record across a tournament.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Standing {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub total_max_chain: u32,
+    pub garbage_sent: u32,
+    pub rating: f64,
+}
+
+impl Default for Standing {
+    fn default() -> Self {
+        Standing {
+            games: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            total_max_chain: 0,
+            garbage_sent: 0,
+            rating: INITIAL_RATING,
+        }
+    }
+}
+
+impl Standing {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games as f64
+    }
+
+    pub fn average_max_chain(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.total_max_chain as f64 / self.games as f64
+    }
+}
+
+/// Every entrant plays every other one `games` times from each side.
+pub struct Tournament {
+    pub entrants: Vec<Entrant>,
+    pub games: u32,
+    pub seed: u64,
+    pub max_frames: u32,
+    pub time_limit: Duration,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>) -> Tournament {
+        Tournament {
+            entrants,
+            games: 10,
+            seed: 0,
+            max_frames: 100_000,
+            time_limit: Duration::from_millis(1000),
+        }
+    }
+
+    /// Play the whole tournament, calling `on_result` after each game with
+    /// the indices of the entrants on each side.
+    pub fn run<F>(&self, mut on_result: F) -> io::Result<Vec<Standing>>
+            where F: FnMut([usize; 2], &MatchResult) {
+        let mut standings = vec![Standing::default(); self.entrants.len()];
+        for first in 0..self.entrants.len() {
+            for second in first + 1..self.entrants.len() {
+                for game in 0..self.games {
+                    let seed = self.seed.wrapping_add(game as u64);
+                    // the same pairs from both sides, so neither side is
+                    // luckier than the other
+                    for sides in [[first, second], [second, first]].iter() {
+                        let result = self.play(*sides, seed)?;
+                        record(&mut standings, *sides, &result);
+                        on_result(*sides, &result);
+                    }
+                }
+            }
+        }
+        Ok(standings)
+    }
+
+    fn play(&self, sides: [usize; 2], seed: u64) -> io::Result<MatchResult> {
+        let one = self.entrants[sides[0]].controller(
+            seed.wrapping_mul(2), self.time_limit)?;
+        let two = self.entrants[sides[1]].controller(
+            seed.wrapping_mul(2).wrapping_add(1), self.time_limit)?;
+        Ok(play_match(one, two, seed, self.max_frames))
+    }
+}
+
+/// How likely a player rated `rating` is to beat one rated `opponent`.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+fn record(standings: &mut [Standing], sides: [usize; 2],
+          result: &MatchResult) {
+    // unfinished games count as draws
+    let points = match result.outcome {
+        Some(Outcome::Winner(0)) => [1.0, 0.0],
+        Some(Outcome::Winner(_)) => [0.0, 1.0],
+        Some(Outcome::Draw) | None => [0.5, 0.5],
+    };
+    let ratings = [standings[sides[0]].rating, standings[sides[1]].rating];
+    for side in 0..2 {
+        let standing = &mut standings[sides[side]];
+        standing.games += 1;
+        match result.outcome {
+            Some(Outcome::Winner(winner)) if winner == side => {
+                standing.wins += 1
+            }
+            Some(Outcome::Winner(_)) => standing.losses += 1,
+            Some(Outcome::Draw) | None => standing.draws += 1,
+        }
+        standing.total_max_chain += result.max_chains[side];
+        standing.garbage_sent += result.garbage_sent[side];
+        let expected = expected_score(ratings[side], ratings[1 - side]);
+        standing.rating += RATING_K * (points[side] - expected);
+    }
+}
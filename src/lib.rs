@@ -7,7 +7,15 @@ use wasm_bindgen::prelude::*;
 use web_sys::{Document, Element};
 
 use puyo_game::game;
-use puyo_game::game::color::Color;
+use puyo_game::game::color::{pair_from_str, pair_to_string, Color};
+use puyo_game::game::{Controller, Input, Puzzle, PuzzleState};
+use puyo_game::game::{Board, ChainSteps, FieldCode};
+use puyo_game::game::{Battle, Targeting};
+use puyo_game::game::controller::Keyboard;
+use puyo_game::ai::{chain_potential, hint, Cpu, Difficulty, Hint};
+use puyo_game::ai::{Flag, Review, ReviewedMove};
+use puyo_game::ai::review::review_versus;
+use puyo_game::ai::{daily, generate};
 
 mod utils;
 
@@ -91,6 +99,14 @@ impl GameCell {
         self.image.set_attribute("style", &Self::style(color))?;
         Ok(())
     }
+
+    /// Show `color` faded out, for a puyo that isn't really there.
+    pub fn set_ghost(&mut self, color: Color) -> Result<(), JsValue> {
+        self.set_color(color)?;
+        self.image.set_attribute("style",
+            &format!("{}; opacity: 0.4", Self::style(color)))?;
+        Ok(())
+    }
 }
 
 pub struct GameView {
@@ -100,6 +116,10 @@ pub struct GameView {
     score: Element,
     next_pivot: GameCell,
     next_wheel: GameCell,
+    potential: Element,
+    potential_for: Option<u32>,
+    hint: Option<Hint>,
+    hint_for: Option<u32>,
 }
 
 impl GameView {
@@ -128,12 +148,16 @@ impl GameView {
         }
         // main board
         let mut board = Vec::with_capacity(game::BOARD_HEIGHT);
-        for _y in 0..game::BOARD_HEIGHT {
+        for row_index in 0..game::BOARD_HEIGHT {
             let html_row = document.create_element("tr")?;
             table.append_child(&html_row)?;
             let mut row = Vec::with_capacity(game::BOARD_WIDTH);
-            for _x in 0..game::BOARD_WIDTH {
+            for x in 0..game::BOARD_WIDTH {
                 let cell = GameCell::new(document)?;
+                // board coordinates, for click handlers
+                let y = game::BOARD_HEIGHT - 1 - row_index;
+                cell.element().set_attribute("data-x", &x.to_string())?;
+                cell.element().set_attribute("data-y", &y.to_string())?;
                 html_row.append_child(cell.element())?;
                 row.push(cell);
             }
@@ -175,6 +199,22 @@ impl GameView {
         html_row.append_child(next_pivot.element())?;
         let next_wheel = GameCell::new(document)?;
         html_row.append_child(next_wheel.element())?;
+        // chain potential
+        let html_row = document.create_element("tr")?;
+        table.append_child(&html_row)?;
+        html_row.set_attribute("style",
+            "border-top: 1px solid black;
+        ")?;
+        let td = document.create_element("td")?;
+        html_row.append_child(&td)?;
+        td.set_attribute("colspan", &game::BOARD_WIDTH.to_string())?;
+        let potential = document.create_element("div")?;
+        td.append_child(&potential)?;
+        potential.set_attribute("style",
+            "height: 20px;
+             font-size: 16px;
+             white-space: nowrap;
+        ")?;
 
         Ok(GameView {
             board,
@@ -183,6 +223,10 @@ impl GameView {
             score,
             next_pivot,
             next_wheel,
+            potential,
+            potential_for: None,
+            hint: None,
+            hint_for: None,
         })
     }
 
@@ -200,6 +244,117 @@ impl GameView {
         )
     }
 
+    /// Ghost the suggested placement for the pair under control, until that
+    /// pair has landed.
+    pub fn show_hint(&mut self, game: &game::Game) {
+        self.hint = hint(game);
+        self.hint_for = Some(game.pair_number());
+        if let Some(hint) = &self.hint {
+            self.potential.set_inner_html(&format!("Hint: {}", hint.reason));
+        }
+    }
+
+    /// Show one placement from a `Review`: the board it went on, the pair
+    /// where it was played and a ghost where the search would have put it.
+    pub fn show_review(&mut self, reviewed: &ReviewedMove, index: usize,
+                       total: usize) -> Result<(), JsValue> {
+        self.paint_board(&reviewed.stack)?;
+        if !reviewed.is_best() {
+            for (coord, color) in reviewed.best_landing.iter() {
+                self.cell(coord).set_ghost(*color)?;
+            }
+        }
+        for (coord, color) in reviewed.played_landing.iter() {
+            self.cell(coord).set_color(*color)?;
+        }
+        let placement = |(column, orientation): (usize, game::Direction)| {
+            format!("{} {:?}", column + 1, orientation).to_lowercase()
+        };
+        let mut text = format!("Move {}/{}: played {}", index + 1, total,
+                               placement(reviewed.played));
+        if !reviewed.is_best() {
+            text += &format!(", best {}", placement(reviewed.best));
+        }
+        for flag in reviewed.flags.iter() {
+            text += &match flag {
+                Flag::PotentialDrop { before, after } => format!(
+                    "; potential fell from {} to {}", before, after),
+                Flag::MissedCounter { pending, garbage } => format!(
+                    "; missed a counter of {} against {}", garbage, pending),
+            };
+        }
+        self.potential.set_inner_html(&text);
+        Ok(())
+    }
+
+    /// Show one step of a chain: the board, the cells about to pop faded
+    /// out, the score so far and what is happening.
+    pub fn show_step(&mut self, board: &game::Board,
+                     popping: &[game::util::Coord], score: u32, text: &str)
+            -> Result<(), JsValue> {
+        self.paint_board(board)?;
+        for coord in popping {
+            let color = board.get(coord).unwrap_or(Color::Empty);
+            self.cell(coord).set_ghost(color)?;
+        }
+        self.score.set_inner_html(&score.to_string());
+        self.potential.set_inner_html(text);
+        Ok(())
+    }
+
+    /// Show a layout being edited, the pair that would come first and a
+    /// caption.
+    pub fn show_editor(&mut self, board: &game::Board, next: (Color, Color),
+                       text: &str) -> Result<(), JsValue> {
+        self.paint_board(board)?;
+        self.next_pivot.set_color(next.0)?;
+        self.next_wheel.set_color(next.1)?;
+        self.score.set_inner_html("");
+        self.potential.set_inner_html(text);
+        Ok(())
+    }
+
+    // show a board that isn't part of a running game
+    fn paint_board(&mut self, board: &game::Board) -> Result<(), JsValue> {
+        self.hint = None;
+        self.potential_for = None;
+        for y in 0..game::BOARD_HEIGHT {
+            for x in 0..game::BOARD_WIDTH {
+                let coord = game::util::Coord { x: x as i32, y: y as i32 };
+                let color = board.get(&coord).unwrap_or(Color::Empty);
+                self.cell(&coord).set_color(color)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn cell(&mut self, coord: &game::util::Coord) -> &mut GameCell {
+        let top_row = game::BOARD_HEIGHT - 1;
+        &mut self.board[top_row - coord.y as usize][coord.x as usize]
+    }
+
+    fn render_hint(&mut self, game: &game::Game) -> Result<(), JsValue> {
+        let hint = match self.hint.take() {
+            Some(hint) => hint,
+            None => return Ok(()),
+        };
+        let board = game.board();
+        if self.hint_for != Some(game.pair_number()) {
+            for (coord, _) in hint.landing.iter() {
+                let color = board.get(coord).unwrap_or(Color::Empty);
+                self.cell(coord).set_color(color)?;
+            }
+            return Ok(());
+        }
+        for (coord, color) in hint.landing.iter() {
+            if board.get(coord) == Some(Color::Empty) {
+                self.cell(coord).set_ghost(*color)?;
+            }
+        }
+        self.hint = Some(hint);
+        Ok(())
+    }
+
     pub fn render(&mut self, game: &game::Game) -> Result<(), JsValue> {
         // render score
         self.score.set_inner_html(&game.score().to_string());
@@ -237,24 +392,333 @@ impl GameView {
         let (next_pivot, next_wheel) = game.next_puyo();
         self.next_pivot.set_color(next_pivot)?;
         self.next_wheel.set_color(next_wheel)?;
+        self.render_hint(game)?;
+        // the stack only settles between pairs, so that's when to look
+        if self.potential_for != Some(game.pair_number()) {
+            self.potential_for = Some(game.pair_number());
+            let text = match chain_potential(&game.stack()) {
+                Some(potential) => format!(
+                    "Potential: {} chain, {} {:?} in column {}",
+                    potential.result.chains, potential.count,
+                    potential.color, potential.trigger.x + 1),
+                None => "Potential: none".to_string(),
+            };
+            self.potential.set_inner_html(&text);
+        }
         Ok(())
     }
 }
 
+// endless play speeds up a level for every so many pairs, each level taking
+// a few ticks off the drop interval down to a floor
+const PAIRS_PER_LEVEL: u32 = 20;
+const START_DROP_INTERVAL: u32 = 50;
+const DROP_INTERVAL_STEP: u32 = 5;
+const MIN_DROP_INTERVAL: u32 = 8;
+
+/// One board on its own, played until it tops out and getting faster as it
+/// goes.
+#[wasm_bindgen]
+pub struct SinglePlayerGame {
+    game: game::Game,
+    keyboard: Keyboard,
+    best: u32,
+    status: Element,
+    view: GameView,
+}
+
+#[wasm_bindgen]
+impl SinglePlayerGame {
+    pub fn new() -> Result<SinglePlayerGame, JsValue> {
+        utils::set_panic_hook();
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+
+        let status = document.create_element("div")?;
+        status.set_attribute("style", "font-size: 24px; margin-bottom: 8px")?;
+        body.append_child(&status)?;
+        let view = GameView::new(&document)?;
+        body.append_child(view.element())?;
+        Ok(SinglePlayerGame {
+            game: game::Game::new(),
+            keyboard: Keyboard::new(),
+            best: 0,
+            status,
+            view,
+        })
+    }
+
+    pub fn restart(&mut self) {
+        self.game = game::Game::new();
+        self.keyboard.reset();
+    }
+
+    /// Starting from 1, one more for every `PAIRS_PER_LEVEL` pairs.
+    pub fn level(&self) -> u32 {
+        self.game.pair_number() / PAIRS_PER_LEVEL + 1
+    }
+
+    pub fn tick(&mut self) -> Result<bool, JsValue> {
+        self.best = self.best.max(self.game.score());
+        if self.game.is_over() {
+            self.view.set_over(self.game.score(), false);
+            return Ok(false);
+        }
+        let faster = (self.level() - 1) * DROP_INTERVAL_STEP;
+        self.game.set_drop_interval(START_DROP_INTERVAL.saturating_sub(faster)
+                                    .max(MIN_DROP_INTERVAL));
+        self.game.tick_with(&mut self.keyboard);
+        self.view.render(&self.game)?;
+        self.status.set_inner_html(&format!(
+            "Level {} &middot; best {}", self.level(), self.best));
+        Ok(true)
+    }
+
+    /// Show where the engine would put the pair under control.
+    pub fn hint(&mut self) {
+        self.view.show_hint(&self.game);
+    }
+
+    pub fn left(&mut self) {
+        self.keyboard.feed(Input::Left);
+    }
+
+    pub fn right(&mut self) {
+        self.keyboard.feed(Input::Right);
+    }
+
+    pub fn up(&mut self) {
+        self.keyboard.feed(Input::Up);
+    }
+
+    pub fn down(&mut self) {
+        self.keyboard.feed(Input::Down);
+    }
+
+    pub fn rotate(&mut self) {
+        self.keyboard.feed(Input::Rotate);
+    }
+}
+
+/// Three or four boards side by side, each aiming its garbage at one of the
+/// others, or two teams of two. Player 1 is on the keyboard and the rest
+/// start as the CPU.
+#[wasm_bindgen]
+pub struct BattleGame {
+    battle: Battle,
+    labels: Vec<Element>,
+    views: Vec<GameView>,
+}
+
+fn place_name(place: usize) -> &'static str {
+    match place {
+        1 => "1st",
+        2 => "2nd",
+        3 => "3rd",
+        _ => "4th",
+    }
+}
+
+#[wasm_bindgen]
+impl BattleGame {
+    pub fn new(players: u32) -> Result<BattleGame, JsValue> {
+        let players = players.clamp(3, 4) as usize;
+        BattleGame::with_teams((0..players).collect())
+    }
+
+    /// Players 1 and 2 against 3 and 4, each pair sharing its garbage.
+    pub fn teams() -> Result<BattleGame, JsValue> {
+        BattleGame::with_teams(vec![0, 0, 1, 1])
+    }
+
+    fn with_teams(teams: Vec<usize>) -> Result<BattleGame, JsValue> {
+        utils::set_panic_hook();
+        let players = teams.len();
+        let mut controllers: Vec<Box<dyn Controller>> =
+            vec![Box::new(Keyboard::new())];
+        for _ in 1..players {
+            controllers.push(Box::new(Cpu::new(Difficulty::Normal)));
+        }
+        let battle = Battle::new_teams(controllers, teams);
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+
+        let row = document.create_element("div")?;
+        row.set_attribute("style", "display: flex; gap: 40px")?;
+        body.append_child(&row)?;
+        let mut labels = Vec::with_capacity(players);
+        let mut views = Vec::with_capacity(players);
+        let in_teams = (0..players).any(|player| {
+            battle.members(battle.team(player)).count() > 1
+        });
+        let mut team_rows: Vec<(usize, Element)> = Vec::new();
+        for player in 0..players {
+            // teammates go in a box together, under their team's name
+            let team = battle.team(player);
+            let parent = if !in_teams {
+                row.clone()
+            } else if let Some((_, team_row)) = team_rows.iter()
+                    .find(|(other, _)| *other == team) {
+                team_row.clone()
+            } else {
+                let group = document.create_element("div")?;
+                group.set_attribute("style",
+                    "border: 2px solid black; padding: 8px")?;
+                row.append_child(&group)?;
+                let heading = document.create_element("div")?;
+                heading.set_attribute("style", "font-size: 24px")?;
+                heading.set_inner_html(&format!("Team {}", team_rows.len() + 1));
+                group.append_child(&heading)?;
+                let team_row = document.create_element("div")?;
+                team_row.set_attribute("style", "display: flex; gap: 20px")?;
+                group.append_child(&team_row)?;
+                team_rows.push((team, team_row.clone()));
+                team_row
+            };
+            let column = document.create_element("div")?;
+            parent.append_child(&column)?;
+            let label = document.create_element("div")?;
+            label.set_attribute("style", "font-size: 20px; height: 28px")?;
+            column.append_child(&label)?;
+            let view = GameView::new(&document)?;
+            column.append_child(view.element())?;
+            labels.push(label);
+            views.push(view);
+        }
+        Ok(BattleGame { battle, labels, views })
+    }
+
+    /// Start over, with everyone aiming the way they were.
+    pub fn restart(&mut self) {
+        let targeting: Vec<Targeting> = (0..self.battle.players())
+            .map(|player| self.battle.targeting(player))
+            .collect();
+        self.battle.restart();
+        // made again as changes, so the new match replays from its seed
+        for (player, targeting) in targeting.into_iter().enumerate() {
+            if targeting != Targeting::Attacker {
+                self.battle.set_targeting(player, targeting);
+            }
+        }
+    }
+
+    /// Hand `player` (from 1) to a person with `level` 0, or to the CPU
+    /// with `level` 1 to 3 for easy to hard.
+    pub fn set_player(&mut self, player: u32, level: u32) {
+        let controller: Box<dyn Controller> = match level {
+            1 => Box::new(Cpu::new(Difficulty::Easy)),
+            2 => Box::new(Cpu::new(Difficulty::Normal)),
+            3 => Box::new(Cpu::new(Difficulty::Hard)),
+            _ => Box::new(Keyboard::new()),
+        };
+        if let Some(player) = self.player_index(player) {
+            self.battle.set_controller(player, controller);
+        }
+    }
+
+    /// Pick how `player` (from 1) aims: 0 at their attacker, 1 at random,
+    /// 2 at the highest score, or 3 always at `target` (from 1), who has to
+    /// be on the other team.
+    pub fn set_targeting(&mut self, player: u32, targeting: u32, target: u32) {
+        let player = match self.player_index(player) {
+            Some(player) => player,
+            None => return,
+        };
+        let opponent = self.player_index(target).filter(|target| {
+            self.battle.team(*target) != self.battle.team(player)
+        });
+        let targeting = match (targeting, opponent) {
+            (0, _) => Targeting::Attacker,
+            (1, _) => Targeting::Random,
+            (2, _) => Targeting::HighestScore,
+            (3, Some(target)) => Targeting::Manual(target),
+            _ => return,
+        };
+        self.battle.set_targeting(player, targeting);
+    }
+
+    fn player_index(&self, player: u32) -> Option<usize> {
+        Some(player as usize)
+            .filter(|player| (1..=self.battle.players()).contains(player))
+            .map(|player| player - 1)
+    }
+
+    pub fn tick(&mut self) -> Result<bool, JsValue> {
+        let outcome = self.battle.tick();
+        let players = self.battle.players();
+        let eliminated = self.battle.eliminated();
+        let solo = (0..players).all(|player| self.battle.team(player) == player);
+        for player in 0..players {
+            let game = self.battle.game(player);
+            let won = outcome == Some(game::Outcome::Winner(
+                self.battle.team(player)));
+            let text = match eliminated.iter().position(|p| *p == player) {
+                _ if won => format!("P{}: wins", player + 1),
+                Some(order) if solo => format!("P{}: out, {}", player + 1,
+                                               place_name(players - order)),
+                Some(_) => format!("P{}: out", player + 1),
+                None if outcome.is_some() => format!("P{}", player + 1),
+                None => {
+                    let aim = match self.battle.targeting(player) {
+                        Targeting::Attacker => "attacker",
+                        Targeting::Random => "random",
+                        Targeting::HighestScore => "top score",
+                        Targeting::Manual(_) => "picked",
+                    };
+                    match self.battle.target(player) {
+                        Some(target) => format!("P{} &rarr; P{} ({})",
+                                                player + 1, target + 1, aim),
+                        None => format!("P{}", player + 1),
+                    }
+                }
+            };
+            self.labels[player].set_inner_html(&text);
+            self.views[player].render(game)?;
+            if outcome.is_some() || game.is_over() {
+                self.views[player].set_over(game.score(), won);
+            }
+        }
+        Ok(outcome.is_none())
+    }
+
+    pub fn left(&mut self) {
+        self.battle.controller(0).feed(Input::Left);
+    }
+
+    pub fn right(&mut self) {
+        self.battle.controller(0).feed(Input::Right);
+    }
+
+    pub fn up(&mut self) {
+        self.battle.controller(0).feed(Input::Up);
+    }
+
+    pub fn down(&mut self) {
+        self.battle.controller(0).feed(Input::Down);
+    }
+
+    pub fn rotate(&mut self) {
+        self.battle.controller(0).feed(Input::Rotate);
+    }
+}
+
 #[wasm_bindgen]
 pub struct TwoPlayerGame {
-    player_one: game::Game,
-    player_two: game::Game,
+    versus: game::Versus,
     view_one: GameView,
     view_two: GameView,
+    // the player being reviewed, their review and the move on screen
+    review: Option<(usize, Review, usize)>,
 }
 
 #[wasm_bindgen]
 impl TwoPlayerGame {
     pub fn new() -> Result<TwoPlayerGame, JsValue> {
         utils::set_panic_hook();
-        let player_one = game::Game::new();
-        let player_two = game::Game::new();
+        let versus = game::Versus::new(
+            Box::new(Keyboard::new()), Box::new(Keyboard::new()));
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
         let body = document.body().expect("document should have a body");
@@ -269,75 +733,655 @@ impl TwoPlayerGame {
         let view_two = GameView::new(&document)?;
         body.append_child(view_two.element())?;
         Ok(TwoPlayerGame {
-            player_one,
-            player_two,
+            versus,
             view_one, view_two,
+            review: None,
         })
     }
 
     pub fn restart(&mut self) {
-        self.player_one = game::Game::new();
-        self.player_two = game::Game::new();
+        self.versus.restart();
+        self.review = None;
+    }
+
+    /// Once the match is over, go through `player`'s (1 or 2) placements
+    /// next to the ones the engine would have made.
+    pub fn review(&mut self, player: u32) -> Result<(), JsValue> {
+        if self.versus.outcome().is_none() || !(1..=2).contains(&player) {
+            return Ok(());
+        }
+        let player = player as usize - 1;
+        let review = review_versus(
+            self.versus.seed(),
+            [self.versus.recording(0).to_vec(),
+             self.versus.recording(1).to_vec()],
+            player,
+            self.versus.game(player).frame());
+        self.review = Some((player, review, 0));
+        self.review_step(0)
+    }
+
+    /// Move `delta` placements forwards or back through the review.
+    pub fn review_step(&mut self, delta: i32) -> Result<(), JsValue> {
+        let (player, review, index) = match &mut self.review {
+            Some(review) => review,
+            None => return Ok(()),
+        };
+        let total = review.moves.len();
+        if total == 0 {
+            return Ok(());
+        }
+        *index = (*index as i64 + delta as i64)
+            .max(0).min(total as i64 - 1) as usize;
+        let view = match player {
+            0 => &mut self.view_one,
+            _ => &mut self.view_two,
+        };
+        view.show_review(&review.moves[*index], *index, total)
+    }
+
+    /// Hand `player` (1 or 2) to a person with `level` 0, or to the CPU
+    /// with `level` 1 to 3 for easy to hard.
+    pub fn set_player(&mut self, player: u32, level: u32) {
+        let controller: Box<dyn Controller> = match level {
+            1 => Box::new(Cpu::new(Difficulty::Easy)),
+            2 => Box::new(Cpu::new(Difficulty::Normal)),
+            3 => Box::new(Cpu::new(Difficulty::Hard)),
+            _ => Box::new(Keyboard::new()),
+        };
+        match player {
+            1 => self.versus.set_controller(0, controller),
+            2 => self.versus.set_controller(1, controller),
+            _ => (),
+        }
     }
 
     pub fn tick(&mut self) -> Result<bool, JsValue> {
-        if self.player_one.is_over() {
-            self.view_one.set_over(self.player_one.score(), false);
-            self.view_two.set_over(self.player_two.score(), true);
-            return Ok(false);
-        } else if self.player_two.is_over() {
-            self.view_one.set_over(self.player_one.score(), true);
-            self.view_two.set_over(self.player_two.score(), false);
+        if let Some(outcome) = self.versus.outcome() {
+            let (one_won, two_won) = match outcome {
+                game::Outcome::Winner(0) => (true, false),
+                game::Outcome::Winner(_) => (false, true),
+                game::Outcome::Draw => (false, false),
+            };
+            self.view_one.set_over(self.versus.game(0).score(), one_won);
+            self.view_two.set_over(self.versus.game(1).score(), two_won);
             return Ok(false);
         }
 
-        self.player_one.tick();
-        self.player_two.tick();
-        self.player_one.add_garbage(self.player_two.get_garbage());
-        self.player_two.add_garbage(self.player_one.get_garbage());
-        
-        self.view_one.render(&self.player_one)?;
-        self.view_two.render(&self.player_two)?;
+        self.versus.tick();
+
+        self.view_one.render(self.versus.game(0))?;
+        self.view_two.render(self.versus.game(1))?;
         Ok(true)
     }
 
+    /// Show `player` (1 or 2) where the engine would put their pair.
+    pub fn hint(&mut self, player: u32) {
+        match player {
+            1 => self.view_one.show_hint(self.versus.game(0)),
+            2 => self.view_two.show_hint(self.versus.game(1)),
+            _ => (),
+        }
+    }
+
     pub fn p1_left(&mut self) {
-        self.player_one.move_(game::Direction::Left);
+        self.versus.controller(0).feed(Input::Left);
     }
 
     pub fn p1_right(&mut self) {
-        self.player_one.move_(game::Direction::Right);
+        self.versus.controller(0).feed(Input::Right);
     }
 
     pub fn p1_up(&mut self) {
-        self.player_one.move_(game::Direction::Up);
+        self.versus.controller(0).feed(Input::Up);
     }
 
     pub fn p1_down(&mut self) {
-        self.player_one.move_(game::Direction::Down);
+        self.versus.controller(0).feed(Input::Down);
     }
 
     pub fn p1_rotate(&mut self) {
-        self.player_one.rotate();
+        self.versus.controller(0).feed(Input::Rotate);
     }
- 
+
     pub fn p2_left(&mut self) {
-        self.player_two.move_(game::Direction::Left);
+        self.versus.controller(1).feed(Input::Left);
     }
 
     pub fn p2_right(&mut self) {
-        self.player_two.move_(game::Direction::Right);
+        self.versus.controller(1).feed(Input::Right);
+    }
+
+    pub fn p2_up(&mut self) {
+        self.versus.controller(1).feed(Input::Up);
     }
- 
+
+    pub fn p2_down(&mut self) {
+        self.versus.controller(1).feed(Input::Down);
+    }
+
+    pub fn p2_rotate(&mut self) {
+        self.versus.controller(1).feed(Input::Rotate);
+    }
+}
+
+#[wasm_bindgen]
+pub struct PuzzleGame {
+    puzzle: Puzzle,
+    game: game::Game,
+    keyboard: Keyboard,
+    title: Element,
+    view: GameView,
+}
+
+#[wasm_bindgen]
+impl PuzzleGame {
+    /// Start the puzzle written out in `text`, in the format `Puzzle`
+    /// reads.
+    pub fn new(text: &str) -> Result<PuzzleGame, JsValue> {
+        utils::set_panic_hook();
+        let puzzle: Puzzle = text.parse()
+            .map_err(|err| JsValue::from_str(&format!("{}", err)))?;
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+
+        let title = document.create_element("div")?;
+        title.set_attribute("style", "font-size: 24px; margin-bottom: 8px")?;
+        body.append_child(&title)?;
+        let view = GameView::new(&document)?;
+        body.append_child(view.element())?;
+
+        let puzzle_game = PuzzleGame {
+            game: puzzle.start(),
+            puzzle,
+            keyboard: Keyboard::new(),
+            title,
+            view,
+        };
+        puzzle_game.show_title("");
+        Ok(puzzle_game)
+    }
+
+    /// Swap in another puzzle, starting it from the beginning.
+    pub fn load(&mut self, text: &str) -> Result<(), JsValue> {
+        self.puzzle = text.parse()
+            .map_err(|err| JsValue::from_str(&format!("{}", err)))?;
+        self.restart();
+        Ok(())
+    }
+
+    /// Swap in the puzzle of the day for a date.
+    pub fn load_daily(&mut self, year: u32, month: u32, day: u32)
+            -> Result<(), JsValue> {
+        self.puzzle = daily(year, month, day)
+            .map_err(|err| JsValue::from_str(&format!("{}", err)))?;
+        self.restart();
+        Ok(())
+    }
+
+    /// Swap in a new puzzle asking for a `chains` chain with `pairs` pairs.
+    pub fn load_generated(&mut self, seed: u32, chains: u32, pairs: usize)
+            -> Result<(), JsValue> {
+        self.puzzle = generate(seed as u64, chains, pairs)
+            .ok_or_else(|| JsValue::from_str("could not make that puzzle"))?;
+        self.restart();
+        Ok(())
+    }
+
+    pub fn restart(&mut self) {
+        self.game = self.puzzle.start();
+        self.keyboard.reset();
+        self.show_title("");
+    }
+
+    fn show_title(&self, status: &str) {
+        self.title.set_inner_html(&format!(
+            "{}: {} {}", self.puzzle.name, self.puzzle.goal, status));
+    }
+
+    pub fn tick(&mut self) -> Result<bool, JsValue> {
+        match self.puzzle.state(&self.game) {
+            PuzzleState::Solved => {
+                self.show_title("- solved!");
+                return Ok(false);
+            }
+            PuzzleState::Failed => {
+                self.show_title("- failed, press Y to retry");
+                return Ok(false);
+            }
+            PuzzleState::Playing => (),
+        }
+        self.game.tick_with(&mut self.keyboard);
+        self.view.render(&self.game)?;
+        Ok(true)
+    }
+
+    pub fn left(&mut self) {
+        self.keyboard.feed(Input::Left);
+    }
+
+    pub fn right(&mut self) {
+        self.keyboard.feed(Input::Right);
+    }
+
+    pub fn up(&mut self) {
+        self.keyboard.feed(Input::Up);
+    }
+
+    pub fn down(&mut self) {
+        self.keyboard.feed(Input::Down);
+    }
+
+    pub fn rotate(&mut self) {
+        self.keyboard.feed(Input::Rotate);
+    }
+}
+
+// one frame of the chain simulator: a board, the cells about to pop, the
+// score so far and a caption
+struct SimulatorFrame {
+    board: Board,
+    popping: Vec<game::util::Coord>,
+    score: u32,
+    text: String,
+}
+
+fn simulator_frames(board: Board) -> Vec<SimulatorFrame> {
+    let frame = |board, popping, score, text: String| SimulatorFrame {
+        board, popping, score, text,
+    };
+    let mut frames = vec![frame(board, Vec::new(), 0, "Start".to_string())];
+    let mut score = 0;
+    let mut steps = ChainSteps::new(board);
+    for step in steps.by_ref() {
+        let link = format!("Link {}", step.number);
+        if step.fell {
+            frames.push(frame(step.settled, Vec::new(), score,
+                              format!("{}: falls", link)));
+        }
+        let mut groups: Vec<String> = step.groups.iter()
+            .map(|(color, cells)| format!("{} {:?}", cells.len(), color))
+            .collect();
+        if !step.garbage.is_empty() {
+            groups.push(format!("{} garbage", step.garbage.len()));
+        }
+        let popping = step.groups.iter()
+            .flat_map(|(_, cells)| cells.iter().copied())
+            .chain(step.garbage.iter().copied())
+            .collect();
+        frames.push(frame(step.settled, popping, score,
+                          format!("{}: pops {}", link, groups.join(", "))));
+        score = step.breakdown.score();
+        frames.push(frame(step.after, Vec::new(), score,
+                          format!("{}: {}", link, step.breakdown)));
+    }
+    let (end, result) = steps.finish();
+    frames.push(frame(end, Vec::new(), result.score, format!(
+        "Done: {} chain, {} points, {} garbage",
+        result.chains, result.score, result.garbage)));
+    frames
+}
+
+/// Steps through the chain a board sets off, one fall or pop at a time.
+#[wasm_bindgen]
+pub struct ChainSimulator {
+    frames: Vec<SimulatorFrame>,
+    index: usize,
+    view: GameView,
+}
+
+#[wasm_bindgen]
+impl ChainSimulator {
+    /// Start from `text`, a board layout or a simulator field code.
+    pub fn new(text: &str) -> Result<ChainSimulator, JsValue> {
+        utils::set_panic_hook();
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+        let view = GameView::new(&document)?;
+        body.append_child(view.element())?;
+        let mut simulator = ChainSimulator {
+            frames: Vec::new(),
+            index: 0,
+            view,
+        };
+        simulator.load(text)?;
+        Ok(simulator)
+    }
+
+    pub fn load(&mut self, text: &str) -> Result<(), JsValue> {
+        let board = text.parse::<Board>().ok()
+            .or_else(|| text.parse::<FieldCode>().ok().map(|code| code.board))
+            .ok_or_else(|| {
+                JsValue::from_str("not a board layout or a field code")
+            })?;
+        self.frames = simulator_frames(board);
+        self.index = 0;
+        self.show()
+    }
+
+    /// Move `delta` frames forward, or back if negative.
+    pub fn step(&mut self, delta: i32) -> Result<(), JsValue> {
+        let last = self.frames.len() as i32 - 1;
+        self.index = (self.index as i32 + delta).clamp(0, last) as usize;
+        self.show()
+    }
+
+    fn show(&mut self) -> Result<(), JsValue> {
+        let frame = &self.frames[self.index];
+        self.view.show_step(&frame.board, &frame.popping, frame.score,
+                            &frame.text)
+    }
+}
+
+/// Paint a layout cell by cell and set the pairs to come, then play on from
+/// it or just set off whatever chains it holds.
+#[wasm_bindgen]
+pub struct Sandbox {
+    board: Board,
+    queue: Vec<(Color, Color)>,
+    brush: Color,
+    // the game going on from the layout, if it has been started
+    game: Option<game::Game>,
+    keyboard: Keyboard,
+    view: GameView,
+}
+
+#[wasm_bindgen]
+impl Sandbox {
+    pub fn new() -> Result<Sandbox, JsValue> {
+        utils::set_panic_hook();
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+        let view = GameView::new(&document)?;
+        body.append_child(view.element())?;
+        let mut sandbox = Sandbox {
+            board: Board::new(),
+            queue: Vec::new(),
+            brush: Color::Red,
+            game: None,
+            keyboard: Keyboard::new(),
+            view,
+        };
+        sandbox.show()?;
+        Ok(sandbox)
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.game.is_none()
+    }
+
+    /// Pick what clicking paints, by its letter in text layouts.
+    pub fn set_brush(&mut self, letter: &str) -> Result<(), JsValue> {
+        let mut letters = letter.chars();
+        self.brush = match (letters.next().and_then(Color::from_char),
+                            letters.next()) {
+            (Some(color), None) => color,
+            _ => return Err(JsValue::from_str("not a color letter")),
+        };
+        self.show()
+    }
+
+    /// Paint the cell at (`x`, `y`), counting up from the bottom left.
+    pub fn paint(&mut self, x: i32, y: i32) -> Result<(), JsValue> {
+        if self.is_editing() {
+            self.board.set(&game::util::Coord { x, y }, self.brush);
+            self.show()?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<(), JsValue> {
+        self.board = Board::new();
+        self.show()
+    }
+
+    /// The pairs to deal first, pivot then wheel, like `RG BY`.
+    pub fn queue(&self) -> String {
+        let pairs: Vec<String> = self.queue.iter()
+            .map(|pair| pair_to_string(*pair))
+            .collect();
+        pairs.join(" ")
+    }
+
+    pub fn set_queue(&mut self, text: &str) -> Result<(), JsValue> {
+        self.queue = text.split_whitespace()
+            .map(|pair| {
+                pair_from_str(pair)
+                    .ok_or_else(|| JsValue::from_str(
+                        &format!("{:?} is not a pair", pair)))
+            })
+            .collect::<Result<_, _>>()?;
+        self.show()
+    }
+
+    /// The layout and queue as a field code.
+    pub fn save(&self) -> String {
+        FieldCode { board: self.board, pairs: self.queue.clone() }.to_string()
+    }
+
+    /// Read back a field code, or a board layout keeping the queue.
+    pub fn load(&mut self, text: &str) -> Result<(), JsValue> {
+        if let Ok(board) = text.parse::<Board>() {
+            self.board = board;
+        } else {
+            let code = text.parse::<FieldCode>()
+                .map_err(|err| JsValue::from_str(&format!("{}", err)))?;
+            self.board = code.board;
+            self.queue = code.pairs;
+        }
+        self.edit()
+    }
+
+    /// Play on from the layout, dealing the queue and then random pairs
+    /// from `seed`.
+    pub fn play(&mut self, seed: u32) {
+        self.game = Some(game::Game::with_queue(
+            self.board, &self.queue, seed as u64));
+        self.keyboard.reset();
+    }
+
+    /// Let the layout's chains go off, with no pairs to follow.
+    pub fn trigger(&mut self) {
+        self.game = Some(game::Game::with_pairs(self.board, &[]));
+        self.keyboard.reset();
+    }
+
+    /// Stop playing and go back to the layout as it was.
+    pub fn edit(&mut self) -> Result<(), JsValue> {
+        self.game = None;
+        self.show()
+    }
+
+    pub fn tick(&mut self) -> Result<(), JsValue> {
+        if let Some(game) = &mut self.game {
+            game.tick_with(&mut self.keyboard);
+            self.view.render(game)?;
+            if game.is_over() {
+                self.view.set_over(game.score(), false);
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&mut self) -> Result<(), JsValue> {
+        let next = self.queue.first().copied()
+            .unwrap_or((Color::Empty, Color::Empty));
+        let text = format!("Painting {:?}; queue: {}", self.brush,
+                           self.queue());
+        self.view.show_editor(&self.board, next, &text)
+    }
+
+    pub fn left(&mut self) {
+        self.keyboard.feed(Input::Left);
+    }
+
+    pub fn right(&mut self) {
+        self.keyboard.feed(Input::Right);
+    }
+
+    pub fn up(&mut self) {
+        self.keyboard.feed(Input::Up);
+    }
+
+    pub fn down(&mut self) {
+        self.keyboard.feed(Input::Down);
+    }
+
+    pub fn rotate(&mut self) {
+        self.keyboard.feed(Input::Rotate);
+    }
+}
+
+// two players' worth of columns
+const COOP_WIDTH: usize = 2 * game::BOARD_WIDTH;
+
+/// Two people steering pairs on one shared board twice the usual width.
+#[wasm_bindgen]
+pub struct CoopGame {
+    coop: game::Coop<COOP_WIDTH>,
+    board: Vec<Vec<GameCell>>,
+    next: Vec<(GameCell, GameCell)>,
+    status: Element,
+}
+
+#[wasm_bindgen]
+impl CoopGame {
+    pub fn new() -> Result<CoopGame, JsValue> {
+        utils::set_panic_hook();
+        let coop = game::Coop::<COOP_WIDTH>::new(
+            vec![Box::new(Keyboard::new()), Box::new(Keyboard::new())]);
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let body = document.body().expect("document should have a body");
+
+        let status = document.create_element("div")?;
+        status.set_attribute("style", "font-size: 24px; margin-bottom: 8px")?;
+        body.append_child(&status)?;
+        let table = document.create_element("table")?;
+        table.set_attribute("style",
+            "display: inline-block;
+             border: 1px solid black;
+             border-collapse: collapse;
+        ")?;
+        body.append_child(&table)?;
+        let mut board = Vec::with_capacity(game::BOARD_HEIGHT);
+        for _row_index in 0..game::BOARD_HEIGHT {
+            let html_row = document.create_element("tr")?;
+            table.append_child(&html_row)?;
+            let mut row = Vec::with_capacity(COOP_WIDTH);
+            for _x in 0..COOP_WIDTH {
+                let cell = GameCell::new(&document)?;
+                html_row.append_child(cell.element())?;
+                row.push(cell);
+            }
+            board.push(row);
+        }
+        // each player's next pair, under the right of their half
+        let html_row = document.create_element("tr")?;
+        table.append_child(&html_row)?;
+        html_row.set_attribute("style",
+            "border-top: 1px solid black;
+        ")?;
+        let mut next = Vec::with_capacity(coop.players());
+        for _player in 0..coop.players() {
+            for _x in 0..(game::BOARD_WIDTH - 2) {
+                let td = document.create_element("td")?;
+                html_row.append_child(&td)?;
+            }
+            let pivot = GameCell::new(&document)?;
+            html_row.append_child(pivot.element())?;
+            let wheel = GameCell::new(&document)?;
+            html_row.append_child(wheel.element())?;
+            next.push((pivot, wheel));
+        }
+        Ok(CoopGame { coop, board, next, status })
+    }
+
+    pub fn restart(&mut self) {
+        self.coop.restart();
+    }
+
+    /// Hand `player` (1 or 2) to a person with `level` 0, or to the CPU
+    /// with `level` 1 to 3 for easy to hard.
+    pub fn set_player(&mut self, player: u32, level: u32) {
+        let controller: Box<dyn Controller> = match level {
+            1 => Box::new(Cpu::new(Difficulty::Easy)),
+            2 => Box::new(Cpu::new(Difficulty::Normal)),
+            3 => Box::new(Cpu::new(Difficulty::Hard)),
+            _ => Box::new(Keyboard::new()),
+        };
+        match player {
+            1 => self.coop.set_controller(0, controller),
+            2 => self.coop.set_controller(1, controller),
+            _ => (),
+        }
+    }
+
+    pub fn tick(&mut self) -> Result<bool, JsValue> {
+        self.coop.tick();
+        let board = self.coop.board();
+        let top_row = game::BOARD_HEIGHT - 1;
+        for (row_index, row) in self.board.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let coord = game::util::Coord {
+                    x: x as i32,
+                    y: (top_row - row_index) as i32,
+                };
+                cell.set_color(board.get(&coord).unwrap_or(Color::Empty))?;
+            }
+        }
+        for (player, (pivot, wheel)) in self.next.iter_mut().enumerate() {
+            let (next_pivot, next_wheel) = self.coop.next_pair(player);
+            pivot.set_color(next_pivot)?;
+            wheel.set_color(next_wheel)?;
+        }
+        let over = if self.coop.is_over() { " &middot; over" } else { "" };
+        self.status.set_inner_html(&format!(
+            "Score {} &middot; best chain {}{}",
+            self.coop.score(), self.coop.max_chain(), over));
+        Ok(!self.coop.is_over())
+    }
+
+    pub fn p1_left(&mut self) {
+        self.coop.controller(0).feed(Input::Left);
+    }
+
+    pub fn p1_right(&mut self) {
+        self.coop.controller(0).feed(Input::Right);
+    }
+
+    pub fn p1_up(&mut self) {
+        self.coop.controller(0).feed(Input::Up);
+    }
+
+    pub fn p1_down(&mut self) {
+        self.coop.controller(0).feed(Input::Down);
+    }
+
+    pub fn p1_rotate(&mut self) {
+        self.coop.controller(0).feed(Input::Rotate);
+    }
+
+    pub fn p2_left(&mut self) {
+        self.coop.controller(1).feed(Input::Left);
+    }
+
+    pub fn p2_right(&mut self) {
+        self.coop.controller(1).feed(Input::Right);
+    }
+
     pub fn p2_up(&mut self) {
-        self.player_two.move_(game::Direction::Up);
+        self.coop.controller(1).feed(Input::Up);
     }
 
     pub fn p2_down(&mut self) {
-        self.player_two.move_(game::Direction::Down);
+        self.coop.controller(1).feed(Input::Down);
     }
 
     pub fn p2_rotate(&mut self) {
-        self.player_two.rotate();
+        self.coop.controller(1).feed(Input::Rotate);
     }
 }
@@ -1,15 +1,295 @@
-import { TwoPlayerGame } from "puyo_rust";
+import { TwoPlayerGame, SinglePlayerGame, BattleGame, PuzzleGame,
+         ChainSimulator, Sandbox, CoopGame } from "puyo_rust";
+import { PUZZLES } from "./puzzles.js";
 
-const game = TwoPlayerGame.new();
-const renderLoop = () => {
-    if (game.tick()) {
-        requestAnimationFrame(renderLoop);
-    }
+// open the page with #solo on the end for endless play alone, #battle or
+// #battle3 for four or three players, #teams for two against two, #coop
+// for two players on one board, #puzzle for the puzzle screen, #chain for
+// the chain simulator or #sandbox for the board editor
+const mode = window.location.hash.slice(1);
+
+const startVersus = () => {
+    const game = TwoPlayerGame.new();
+    const renderLoop = () => {
+        if (game.tick()) {
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    requestAnimationFrame(renderLoop);
+    document.onkeydown = versusKeys(game, renderLoop);
 };
 
-requestAnimationFrame(renderLoop);
+const startSolo = () => {
+    const game = SinglePlayerGame.new();
+    let running = true;
+    const renderLoop = () => {
+        running = game.tick();
+        if (running) {
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    requestAnimationFrame(renderLoop);
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 37 || e.keyCode == 65) {
+            game.left();
+        } else if (e.keyCode == 39 || e.keyCode == 68) {
+            game.right();
+        } else if (e.keyCode == 38 || e.keyCode == 87) {
+            game.up();
+        } else if (e.keyCode == 40 || e.keyCode == 83) {
+            game.down();
+        } else if (e.keyCode == 32 || e.keyCode == 13) {
+            game.rotate();
+        } else if (e.keyCode == 72 || e.keyCode == 191) {
+            game.hint();
+        } else if (e.keyCode == 89) {
+            game.restart();
+            if (!running) {
+                running = true;
+                requestAnimationFrame(renderLoop);
+            }
+        }
+    });
+};
 
-document.onkeydown = ((e) => {
+const startBattle = (game) => {
+    let running = true;
+    const renderLoop = () => {
+        running = game.tick();
+        if (running) {
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    requestAnimationFrame(renderLoop);
+    // T cycles how player 1 aims: attacker, random, top score
+    let targeting = 0;
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 37 || e.keyCode == 65) {
+            game.left();
+        } else if (e.keyCode == 39 || e.keyCode == 68) {
+            game.right();
+        } else if (e.keyCode == 38 || e.keyCode == 87) {
+            game.up();
+        } else if (e.keyCode == 40 || e.keyCode == 83) {
+            game.down();
+        } else if (e.keyCode == 32 || e.keyCode == 13) {
+            game.rotate();
+        } else if (e.keyCode == 84) {
+            targeting = (targeting + 1) % 3;
+            game.set_targeting(1, targeting, 0);
+        } else if (e.keyCode >= 50 && e.keyCode <= 52) {
+            // 2 to 4 aim player 1 at that player, unless on the same team
+            game.set_targeting(1, 3, e.keyCode - 48);
+        } else if (e.keyCode == 89) {
+            game.restart();
+            if (!running) {
+                running = true;
+                requestAnimationFrame(renderLoop);
+            }
+        }
+    });
+};
+
+// player 1 on WASD and space, player 2 on the arrows and enter
+const startCoop = () => {
+    const game = CoopGame.new();
+    let running = true;
+    const renderLoop = () => {
+        running = game.tick();
+        if (running) {
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    requestAnimationFrame(renderLoop);
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 65) {
+            game.p1_left();
+        } else if (e.keyCode == 68) {
+            game.p1_right();
+        } else if (e.keyCode == 87) {
+            game.p1_up();
+        } else if (e.keyCode == 83) {
+            game.p1_down();
+        } else if (e.keyCode == 32) {
+            game.p1_rotate();
+        } else if (e.keyCode == 37) {
+            game.p2_left();
+        } else if (e.keyCode == 39) {
+            game.p2_right();
+        } else if (e.keyCode == 38) {
+            game.p2_up();
+        } else if (e.keyCode == 40) {
+            game.p2_down();
+        } else if (e.keyCode == 13) {
+            game.p2_rotate();
+        } else if (e.keyCode >= 48 && e.keyCode <= 51) {
+            // 0 hands player two back to a person, 1-3 pick a CPU difficulty
+            game.set_player(2, e.keyCode - 48);
+        } else if (e.keyCode == 89) {
+            game.restart();
+            if (!running) {
+                running = true;
+                requestAnimationFrame(renderLoop);
+            }
+        }
+    });
+};
+
+const startPuzzle = () => {
+    let index = 0;
+    const game = PuzzleGame.new(PUZZLES[index]);
+    let running = true;
+    const renderLoop = () => {
+        running = game.tick();
+        if (running) {
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    const rerun = () => {
+        if (!running) {
+            running = true;
+            requestAnimationFrame(renderLoop);
+        }
+    };
+    requestAnimationFrame(renderLoop);
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 37 || e.keyCode == 65) {
+            game.left();
+        } else if (e.keyCode == 39 || e.keyCode == 68) {
+            game.right();
+        } else if (e.keyCode == 38 || e.keyCode == 87) {
+            game.up();
+        } else if (e.keyCode == 40 || e.keyCode == 83) {
+            game.down();
+        } else if (e.keyCode == 32 || e.keyCode == 13) {
+            game.rotate();
+        } else if (e.keyCode == 89) {
+            game.restart();
+            rerun();
+        } else if (e.keyCode == 78 || e.keyCode == 80) {
+            // N and P step through the puzzles
+            const step = e.keyCode == 78 ? 1 : PUZZLES.length - 1;
+            index = (index + step) % PUZZLES.length;
+            game.load(PUZZLES[index]);
+            rerun();
+        } else if (e.keyCode == 84) {
+            // T for today's puzzle
+            const today = new Date();
+            game.load_daily(today.getFullYear(), today.getMonth() + 1,
+                            today.getDate());
+            rerun();
+        } else if (e.keyCode == 71) {
+            // G makes up a fresh 3-chain in 2 pairs
+            game.load_generated(Math.floor(Math.random() * 0xffffffff), 3, 2);
+            rerun();
+        }
+    });
+};
+
+// a 3-chain of stacked columns, to have something to step through
+const SAMPLE_CHAIN = `
+G.....
+RB....
+RGB...
+RGB...
+RGB...`;
+
+const startChain = () => {
+    const simulator = ChainSimulator.new(SAMPLE_CHAIN);
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 37 || e.keyCode == 219) {
+            simulator.step(-1);
+        } else if (e.keyCode == 39 || e.keyCode == 221 || e.keyCode == 32) {
+            simulator.step(1);
+        } else if (e.keyCode == 76) {
+            // L loads a field code
+            const code = window.prompt("Field code");
+            if (code) {
+                try {
+                    simulator.load(code);
+                } catch (err) {
+                    window.alert(err);
+                }
+            }
+        }
+    });
+};
+
+const startSandbox = () => {
+    const sandbox = Sandbox.new();
+    const renderLoop = () => {
+        sandbox.tick();
+        requestAnimationFrame(renderLoop);
+    };
+    requestAnimationFrame(renderLoop);
+    // cells carry their board coordinates; hold the button down to paint
+    // several at once
+    const paintAt = (e) => {
+        const cell = e.target.closest("td[data-x]");
+        if (cell) {
+            sandbox.paint(Number(cell.dataset.x), Number(cell.dataset.y));
+        }
+    };
+    document.onmousedown = paintAt;
+    document.onmouseover = ((e) => {
+        if (e.buttons & 1) {
+            paintAt(e);
+        }
+    });
+    const brushes = { 82: "R", 71: "G", 66: "B", 89: "Y", 80: "P", 79: "O",
+                      69: ".", 8: "." };
+    const ask = (question, answer, apply) => {
+        const text = window.prompt(question, answer);
+        if (text !== null) {
+            try {
+                apply(text);
+            } catch (err) {
+                window.alert(err);
+            }
+        }
+    };
+    document.onkeydown = ((e) => {
+        e = e || window.event;
+        if (e.keyCode == 27) {
+            sandbox.edit();
+        } else if (!sandbox.is_editing()) {
+            if (e.keyCode == 37) {
+                sandbox.left();
+            } else if (e.keyCode == 39) {
+                sandbox.right();
+            } else if (e.keyCode == 38) {
+                sandbox.up();
+            } else if (e.keyCode == 40) {
+                sandbox.down();
+            } else if (e.keyCode == 32) {
+                sandbox.rotate();
+            }
+        } else if (e.keyCode in brushes) {
+            // R G B Y P O pick a color, E or backspace erases
+            sandbox.set_brush(brushes[e.keyCode]);
+        } else if (e.keyCode == 81) {
+            ask("Pairs to deal first, like RG BY", sandbox.queue(),
+                (text) => sandbox.set_queue(text));
+        } else if (e.keyCode == 83) {
+            ask("Field code to copy", sandbox.save(), () => {});
+        } else if (e.keyCode == 76) {
+            ask("Field code to load", "", (text) => sandbox.load(text));
+        } else if (e.keyCode == 67) {
+            sandbox.clear();
+        } else if (e.keyCode == 13) {
+            sandbox.play(Math.floor(Math.random() * 0xffffffff));
+        } else if (e.keyCode == 84) {
+            sandbox.trigger();
+        }
+    });
+};
+
+const versusKeys = (game, renderLoop) => ((e) => {
     e = e || window.event;
     if (e.keyCode == 65){
         game.p1_left();
@@ -31,8 +311,41 @@ document.onkeydown = ((e) => {
         game.p2_down();
     } else if (e.keyCode == 13) {
         game.p2_rotate();
+    } else if (e.keyCode >= 48 && e.keyCode <= 51) {
+        // 0 hands player two back to a person, 1-3 pick a CPU difficulty
+        game.set_player(2, e.keyCode - 48);
+    } else if (e.keyCode == 72) {
+        game.hint(1);
+    } else if (e.keyCode == 191) {
+        game.hint(2);
+    } else if (e.keyCode == 82) {
+        game.review(1);
+    } else if (e.keyCode == 69) {
+        game.review(2);
+    } else if (e.keyCode == 219) {
+        game.review_step(-1);
+    } else if (e.keyCode == 221) {
+        game.review_step(1);
     } else if (e.keyCode == 89) {
         game.restart();
         requestAnimationFrame(renderLoop);
     }
 });
+
+if (mode == "solo") {
+    startSolo();
+} else if (mode == "battle" || mode == "battle3") {
+    startBattle(BattleGame.new(mode == "battle3" ? 3 : 4));
+} else if (mode == "teams") {
+    startBattle(BattleGame.teams());
+} else if (mode == "coop") {
+    startCoop();
+} else if (mode == "puzzle") {
+    startPuzzle();
+} else if (mode == "chain") {
+    startChain();
+} else if (mode == "sandbox") {
+    startSandbox();
+} else {
+    startVersus();
+}
@@ -0,0 +1,35 @@
+// Puzzles for the puzzle screen, in the text format of puyo_game::game::Puzzle.
+export const PUZZLES = [
+`name: First steps
+goal: chain 2
+pairs: GR
+board:
+R.....
+R.....
+G.....
+GGR...`,
+
+`name: All clear
+goal: clear all
+pairs: BB BY
+board:
+B.....
+Y.B...
+YBY...`,
+
+`name: Dig out
+goal: clear garbage
+pairs: GG RR
+board:
+G.....
+R.OO..
+RGRO..`,
+
+`name: Greens only
+goal: pop 8 G
+pairs: GG RG
+board:
+G...G.
+R.R.G.
+GRRGR.`,
+];